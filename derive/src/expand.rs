@@ -1,36 +1,98 @@
 use proc_macro2::{Span, TokenStream, TokenTree};
-use quote::{ToTokens, quote};
+use quote::{ToTokens, quote, quote_spanned};
 use syn::parse::Parse;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
 use syn::visit_mut::{self, VisitMut};
 use syn::{
     Data, DeriveInput, Error, Expr, Field, Fields, Ident, Path, Result, Token, Type,
     Visibility, parse_quote, token,
 };
 
+mod view;
+
+pub use view::view;
+
 type Punctuated = syn::punctuated::Punctuated<Field, Token![,]>;
 
-pub fn readonly(mut input: DeriveInput) -> Result<TokenStream> {
+pub fn readonly(args: ItemArgs, mut input: DeriveInput) -> Result<TokenStream> {
     let call_site = Span::call_site();
 
-    match &input.data {
-        Data::Struct(data) => {
-            if data.fields.iter().count() == 0 {
-                return Err(Error::new(call_site, "input must be a struct with fields"));
-            }
-        }
-        Data::Enum(_) | Data::Union(_) => {
-            return Err(Error::new(call_site, "input must be a struct"));
-        }
+    if let Some(span) = args.view_ids_span {
+        return Err(Error::new(span, "`id(..)` lists the id fields of a `view`"));
+    }
+    check_named_struct(&input)?;
+    if let Some(repr) = has_defined_repr(&input).first() {
+        return Err(Error::new_spanned(repr, "Should not have `#[repr]`"));
     }
     let mut attr_errors = Vec::new();
+    let declared_names: Vec<_> = fields_of_input(&mut input)
+        .iter()
+        .map(|f| f.ident.clone().unwrap())
+        .collect();
     let id_field_type = rearange_by_id(&mut input, &mut attr_errors);
+    // looked up by name, `rearange_by_id` moved the id fields first
+    let declared_types: Vec<_> = declared_names
+        .iter()
+        .map(|name| {
+            let fields = fields_of_input(&mut input);
+            fields
+                .iter()
+                .find(|f| f.ident.as_ref() == Some(name))
+                .unwrap()
+                .ty
+                .clone()
+        })
+        .collect();
     let doc = quote! {
         #[cfg(doc)]
         #input
     };
     if id_field_type.is_empty() {
-        return Err(Error::new(call_site, "at least specify one `#[id]`"));
+        return Err(Error::new(input.ident.span(), "at least specify one `#[id]`"));
+    }
+    if let Some((field, _)) = id_field_type
+        .iter()
+        .find(|(field, t)| t.intern.is_some() && !is_symbol(&field.ty))
+    {
+        return Err(Error::new_spanned(
+            &field.ty,
+            "`intern` fields are declared as `mut_set::intern::Symbol`, build it by `Symbol::from(string)`",
+        ));
+    }
+    // a composite id of symbols is `Copy` as they are
+    let interned = id_field_type.iter().filter(|(_, t)| t.intern.is_some()).count();
+    if id_field_type.len() > 1 && interned != 0 && interned != id_field_type.len() {
+        let intern = id_field_type.iter().find_map(|(_, t)| t.intern.as_ref()).unwrap();
+        return Err(Error::new_spanned(
+            intern,
+            "`intern` on a composite id makes it `Copy`, which needs every `#[id]` field interned",
+        ));
+    }
+    // `is_symbol` only reads the name, the type itself is checked by the compiler
+    let symbol_checks =
+        id_field_type
+            .iter()
+            .filter(|(_, t)| t.intern.is_some())
+            .map(|(f, _)| {
+                let ty = &f.ty;
+                quote_spanned! {ty.span()=>
+                    const _: () = mut_set::__private::check_intern_symbol::<#ty>();
+                }
+            });
+    let symbol_checks = quote!(#(#symbol_checks)*);
+    check_compute_id(&args, &id_field_type)?;
+    let cache_hash = args.cache_hash.is_some();
+    if cache_hash {
+        check_cache_hash(&args, &id_field_type)?;
+        // first field of both the item and the id
+        let hash_field: syn::FieldsNamed = parse_quote!({ #[doc(hidden)] __hash: u64 });
+        fields_of_input(&mut input).insert(0, hash_field.named[0].clone());
     }
+    check_flatten(&args, &id_field_type)?;
+    let columns = id_columns(&id_field_type);
+    let id_len = id_field_type.len() + usize::from(cache_hash);
+    let new_item_vis = to_super(&input.vis);
     let mut readonly = input.clone();
     let mut id: syn::DeriveInput = parse_quote! {
         struct Id {}
@@ -39,9 +101,6 @@ pub fn readonly(mut input: DeriveInput) -> Result<TokenStream> {
     readonly.attrs.push(parse_quote!(#[doc(hidden)]));
     id.attrs.clear();
     id.attrs.push(parse_quote!(#[doc(hidden)]));
-    if !has_defined_repr(&input).is_empty() {
-        return Err(Error::new(call_site, "Should not have `#[repr]`"));
-    }
     input.attrs.push(parse_quote!(#[repr(C)]));
     readonly.attrs.push(parse_quote!(#[repr(C)]));
     id.attrs.push(parse_quote!(#[repr(C)]));
@@ -49,203 +108,352 @@ pub fn readonly(mut input: DeriveInput) -> Result<TokenStream> {
     id.vis = input.vis.clone();
     let readonly_fields = fields_of_input(&mut readonly);
     let id_fields = fields_of_input(&mut id);
-    let mut id_func_input = quote!();
-    let mut id_borrow_input = quote!();
     let mut new_id_input = quote!();
     let mut new_id_field = quote!();
-    let mut id_input = quote!();
-    let mut hash_impl = quote!();
-    let mut partial_cmp = quote!();
     for (i, f) in readonly_fields.iter_mut().enumerate() {
         f.attrs.clear();
-        if i < id_field_type.len() {
-            id_fields.push(f.clone());
+        if i < id_len {
+            if cache_hash && i == 0 {
+                id_fields.push(f.clone());
+            }
             f.vis = Visibility::Inherited;
         } else {
             f.vis = to_super(&f.vis);
         }
     }
-    for (f, borrow_type) in id_field_type.iter() {
-        let t = f.ty.clone();
-        let i = f.ident.clone();
-        hash_impl = if let Some(into_hash_ord_fn) = &borrow_type.into_hash_ord_fn {
-            quote! {
-                #hash_impl
-                Hash::hash(&#into_hash_ord_fn(&self.#i), state);
-            }
-        } else {
-            quote! {
-                #hash_impl
-                Hash::hash(&self.#i, state);
-            }
-        };
-        id_func_input = quote! {#i, #id_func_input};
-        id_input = quote!(#i: #t, #id_input);
+    id_fields.extend(columns.iter().map(|column| column.field.clone()));
+    let mut hash_impl = quote!();
+    let mut partial_eq = Vec::new();
+    let mut cmp = Vec::new();
+    for IdColumn { field: f, borrow_type, .. } in &columns {
+        let t = &f.ty;
+        let i = f.ident.as_ref().unwrap();
+        if args.compute_id.is_none() {
+            hash_impl.extend(borrow_type.hash(i));
+            partial_eq.push(borrow_type.eq(i));
+            cmp.push(borrow_type.cmp(i));
+        }
         new_id_input = quote! {#new_id_input #i: #t,};
         new_id_field = quote! {#new_id_field #i,};
-        id_borrow_input = quote!(&#i, #id_borrow_input)
     }
-    let mut id_field_type_rev = id_field_type.clone();
-    id_field_type_rev.reverse();
-    let i = id_field_type_rev[0].0.ident.clone();
-    let mut partial_eq =
-        if let Some(into_hash_ord_fn) = &id_field_type_rev[0].1.into_hash_ord_fn {
-            quote! {
-                #into_hash_ord_fn(&self.#i) == #into_hash_ord_fn(&other.#i)
-            }
-        } else {
-            quote! {
-                self.#i == other.#i
-            }
-        };
-    for (f, borrow_type) in id_field_type_rev.iter().skip(1) {
-        let i = f.ident.clone();
-        (partial_eq, partial_cmp) = if let Some(into_hash_ord_fn) =
-            &borrow_type.into_hash_ord_fn
-        {
-            (
-                quote! { #into_hash_ord_fn(&self.#i) == #into_hash_ord_fn(&other.#i) && #partial_eq },
-                quote! {
-                    match #into_hash_ord_fn(&self.#i).partial_cmp(&#into_hash_ord_fn(&other.#i)) {
-                        Some(core::cmp::Ordering::Equal)|None => {}
-                        ord => return ord,
-                    }
-                    #partial_cmp
-                },
-            )
-        } else {
-            (
-                quote! { self.#i == other.#i && #partial_eq },
-                quote! {
-                    match self.#i.partial_cmp(&other.#i) {
-                        Some(core::cmp::Ordering::Equal)|None => {}
-                        ord => return ord,
-                    }
-                    #partial_cmp
-                },
-            )
-        };
+    if args.compute_id.is_some() {
+        hash_impl = quote! { Hash::hash(&self.__key(), state); };
+        partial_eq.push(quote! { self.__key() == other.__key() });
+        cmp.push(quote! { Ord::cmp(&self.__key(), &other.__key()) });
     }
-    let i = id_field_type_rev[0].0.ident.clone();
-    partial_cmp = if let Some(into_hash_ord_fn) = &id_field_type_rev[0].1.into_hash_ord_fn
-    {
-        quote! {#partial_cmp
-            #into_hash_ord_fn(&self.#i).partial_cmp(&#into_hash_ord_fn(&other.#i))
-        }
-    } else {
-        quote! {#partial_cmp
-            self.#i.partial_cmp(&other.#i)
-        }
-    };
     let ident = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
-    let mut hash_impl_generics: syn::Generics = parse_quote!(#impl_generics);
-    if hash_impl_generics.params.is_empty() {
-        hash_impl_generics = parse_quote!(<S: BuildHasher + Default>);
-    } else {
-        hash_impl_generics
-            .params
-            .insert(0, parse_quote!(S: BuildHasher + Default));
-    }
-    let mut life_hash_impl_generics = hash_impl_generics.clone();
-    life_hash_impl_generics.params.insert(0, parse_quote!('a));
+    id.generics =
+        used_generics(&input.generics, columns.iter().map(|column| &column.field.ty));
+    let (id_impl_generics, id_ty_generics, id_where_clause) =
+        id.generics.split_for_impl();
     let self_path: Path = parse_quote!(#ident #ty_generics);
     for field in readonly_fields {
         ReplaceSelf::new(&self_path).visit_type_mut(&mut field.ty);
     }
 
-    readonly.ident = Ident::new(&format!("IdReadonly{}", input.ident), call_site);
-    id.ident = Ident::new(&format!("{}Id", input.ident), call_site);
+    readonly.ident = args.readonly.clone().unwrap_or_else(|| {
+        Ident::new(&format!("IdReadonly{}", input.ident), input.ident.span())
+    });
+    id.ident = args
+        .id
+        .clone()
+        .unwrap_or_else(|| Ident::new(&format!("{}Id", input.ident), input.ident.span()));
+    check_name_collision(ident, &id.ident, &readonly.ident)?;
+    if let Some(id_vis) = &args.id_vis {
+        id.vis = id_vis.clone();
+    }
     let readonly_ident = &readonly.ident;
     let id_ident = &id.ident;
-    let mod_name =
-        Ident::new(&format!("__{}", to_snake_case(&ident.to_string())), call_site);
+    // bounds of the generic id fields needed by the impls, the fields compared
+    // through functions or `compute_id` are left to the declared bounds
+    let plain_id_types: Vec<_> = columns
+        .iter()
+        .filter(|column| {
+            column.borrow_type.is_plain() && column.borrow_type.source.is_none()
+        })
+        .map(|column| &column.field.ty)
+        .collect();
+    let id_bounded =
+        |bound: TokenStream| with_bounds(&id.generics, &plain_id_types, bound);
+    let item_bounded = |bound: TokenStream| {
+        with_bounds(&input.generics, [quote!(#id_ident #id_ty_generics)], bound)
+    };
+    let item_generics = item_bounded(quote!(Hash + Eq));
+    let (item_impl_generics, _, item_where_clause) = item_generics.split_for_impl();
+    let item_prehashed_generics = item_bounded(quote!(mut_set::PrehashedId + Eq));
+    let (item_prehashed_impl_generics, _, item_prehashed_where_clause) =
+        item_prehashed_generics.split_for_impl();
+    let id_hash_generics = id_bounded(quote!(Hash));
+    let (id_hash_impl_generics, _, id_hash_where_clause) =
+        id_hash_generics.split_for_impl();
+    // named after the item verbatim, distinct names such as `HTTPRoute` and
+    // `HttpRoute` keep distinct modules
+    let mod_name = Ident::new(&format!("__{ident}"), call_site);
 
+    let prehashed = id_field_type.iter().find_map(|(_, t)| t.prehashed.as_ref());
+    let prehashed_impl = match prehashed {
+        Some(prehashed) if id_field_type.len() != 1 => {
+            return Err(Error::new_spanned(
+                prehashed,
+                "`prehashed` needs a single `#[id]` field, which is hashed as it is",
+            ));
+        }
+        Some(_) => {
+            let ty = &id_field_type[0].0.ty;
+            let prehashed_trait = quote_spanned!(ty.span()=> mut_set::Prehashed);
+            // not bounded by `PrehashedId`, so that other id types are reported
+            quote! {
+                impl #item_impl_generics #prehashed_trait for #ident #ty_generics #item_where_clause {
+                    type BuildHasher = mut_set::NoHashBuildHasher;
+                }
+            }
+        }
+        None if cache_hash => quote! {
+            impl #id_hash_impl_generics mut_set::PrehashedId for #id_ident #id_ty_generics #id_hash_where_clause {}
+            impl #item_prehashed_impl_generics mut_set::Prehashed for #ident #ty_generics #item_prehashed_where_clause {
+                type BuildHasher = mut_set::PrehashedBuildHasher;
+            }
+        },
+        None => quote!(),
+    };
     let attr_errors = attr_errors.iter().map(Error::to_compile_error);
     input.attrs.insert(0, parse_quote!(#[cfg(not(doc))]));
-    let (id_define, id_impls) = if id_field_type.len() == 1
-        && id_field_type[0].1.into_hash_ord_fn.is_none()
-    {
+    let aliased_id = id_field_type.len() == 1
+        && id_field_type[0].1.is_plain()
+        && !cache_hash
+        && args.compute_id.is_none();
+    let pointer_borrows = pointer_borrows(
+        &input.generics,
+        quote!(#ident #ty_generics),
+        quote!(#id_ident #id_ty_generics),
+        !aliased_id,
+    );
+    let (id_define, id_impls) = if aliased_id {
+        if let Some(span) = args.derive_id_span {
+            return Err(Error::new(
+                span,
+                "`derive_id` needs a composite id, a single `#[id]` field is used as the id type directly",
+            ));
+        }
+        let id_vis = args.id_vis.clone().unwrap_or(parse_quote!(pub));
         let unique_id_ident = id_field_type[0].0.ident.as_ref().unwrap();
         let unique_id_type = &id_field_type[0].0.ty;
+        let alias_generics = alias_generics(&id.generics);
         let extra_borrow = if let Some(unique_id_borrow_type) =
-            id_field_type[0].1.borrow_type.as_ref()
+            id_field_type[0].1.extra_borrow()
         {
             quote! {
                 impl #impl_generics Borrow<#unique_id_borrow_type> for #ident #ty_generics #where_clause {
                     fn borrow(&self) -> &#unique_id_borrow_type {
-                        &self.#unique_id_ident
+                        Borrow::borrow(&self.#unique_id_ident)
                     }
                 }
             }
         } else {
             quote! {}
         };
+        let from_item = id_field_type[0].1.flatten.is_some().then(|| {
+            let mut ref_generics =
+                with_bounds(&input.generics, [unique_id_type], quote!(Clone));
+            ref_generics.params.insert(0, parse_quote!('__item));
+            let (ref_impl_generics, _, ref_where_clause) = ref_generics.split_for_impl();
+            quote! {
+                impl #ref_impl_generics From<&'__item #ident #ty_generics> for #unique_id_type
+                #ref_where_clause
+                {
+                    #[inline]
+                    fn from(item: &'__item #ident #ty_generics) -> Self {
+                        item.#unique_id_ident.clone()
+                    }
+                }
+            }
+        });
         (
             quote! {
-                pub type #id_ident = #unique_id_type;
+                #id_vis type #id_ident #alias_generics = #unique_id_type;
             },
             quote! {
                 #extra_borrow
-                impl #impl_generics Borrow<#id_ident> for #ident #ty_generics #where_clause {
-                    fn borrow(&self) -> &#id_ident {
+                #from_item
+                impl #impl_generics Borrow<#id_ident #id_ty_generics> for #ident #ty_generics #where_clause {
+                    fn borrow(&self) -> &#id_ident #id_ty_generics {
                         &self.#unique_id_ident
                     }
                 }
             },
         )
     } else {
-        (
-            quote! {
-                #id
-                impl #id_ident {
-                    #[inline]
-                    pub fn new(#new_id_input) -> Self { Self{#new_id_field} }
-                }
+        check_id_vis(&args, &input.vis, ident)?;
+        let mut derive_id = args.derive_id.clone();
+        if interned == id_field_type.len()
+            && !derive_id.iter().any(|path| path.is_ident("Copy"))
+        {
+            derive_id.push(parse_quote!(Copy));
+        }
+        if !derive_id.is_empty() {
+            id.attrs.push(parse_quote!(#[derive(#(#derive_id),*)]));
+        }
+        let id_names: Vec<_> = columns
+            .iter()
+            .map(|column| column.field.ident.as_ref().unwrap())
+            .collect();
+        let id_types: Vec<_> = columns.iter().map(|column| &column.field.ty).collect();
+        let mut id_eq = partial_eq;
+        if cache_hash {
+            id_eq.insert(0, quote! { self.__hash == other.__hash });
+        }
+        let id_struct = IdStruct {
+            ident: id_ident,
+            generics: &id.generics,
+            names: id_names.clone(),
+            types: id_types,
+            bounded: plain_id_types.clone(),
+            hash: if cache_hash {
+                quote! { state.write_u64(self.__hash); }
+            } else {
+                hash_impl.clone()
             },
+            eq: id_eq,
+            cmp,
+            cache_hash,
+        };
+        let new_item_generics =
+            with_bounds(&input.generics, &plain_id_types, quote!(Hash));
+        let (new_item_impl_generics, _, new_item_where_clause) =
+            new_item_generics.split_for_impl();
+        let cached_hash_impl = cache_hash.then(|| {
             quote! {
-                #[doc(hidden)]
-                impl Hash for #id_ident {
+                impl #id_hash_impl_generics #id_ident #id_ty_generics #id_hash_where_clause {
                     #[inline]
-                    fn hash<H: Hasher>(&self, state: &mut H) {
+                    pub fn new(#new_id_input) -> Self {
+                        let mut id = Self { __hash: 0, #new_id_field };
+                        id.__hash = id.__compute_hash();
+                        id
+                    }
+                    fn __compute_hash(&self) -> u64 {
+                        let state = &mut mut_set::__private::FnvHasher::default();
                         #hash_impl
+                        Hasher::finish(state)
                     }
                 }
-                #[doc(hidden)]
-                impl PartialEq for #id_ident {
-                    #[inline]
-                    fn eq(&self, other: &Self) -> bool {
-                        #partial_eq
+                impl #new_item_impl_generics #ident #ty_generics #new_item_where_clause {
+                    /// Create the item, computing the hash of its id
+                    #[allow(clippy::too_many_arguments)]
+                    #new_item_vis fn new(#(#declared_names: #declared_types),*) -> Self {
+                        let mut item = Self { __hash: 0, #(#declared_names),* };
+                        item.__hash = Borrow::<#id_ident #id_ty_generics>::borrow(&item).__compute_hash();
+                        item
                     }
                 }
-                #[doc(hidden)]
-                impl Eq for #id_ident {}
-                #[doc(hidden)]
-                #[allow(clippy::non_canonical_partial_ord_impl)]
-                impl PartialOrd for #id_ident {
-                    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-                        #partial_cmp
+            }
+        });
+        let key_impl = args.compute_id.as_ref().map(|compute_id| {
+            let body = match compute_id {
+                // typed by the parameter of `__key`, which a closure called in
+                // place would not be
+                Expr::Closure(closure) if closure.inputs.len() == 1 => {
+                    let input = &closure.inputs[0];
+                    let body = &closure.body;
+                    quote! {
+                        let #input = self;
+                        #body
                     }
                 }
-                #[doc(hidden)]
-                impl Ord for #id_ident {
-                    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                        self.partial_cmp(other).unwrap_or(std::cmp::Ordering::Equal)
+                _ => quote! { (#compute_id)(self) },
+            };
+            quote! {
+                impl #id_impl_generics #id_ident #id_ty_generics #id_where_clause {
+                    #[inline]
+                    fn __key(&self) -> impl Hash + Ord + '_ {
+                        #body
                     }
                 }
-                impl #impl_generics Borrow<#id_ident> for #ident #ty_generics #where_clause {
-                    fn borrow(&self) -> &#id_ident {
-                        unsafe { &*(self as *const Self as *const #id_ident) }
+            }
+        });
+        let id_struct_impls = id_struct.impls();
+        let from_item = id_struct.ref_conversion(
+            quote!(#ident #ty_generics),
+            &input.generics,
+            columns
+                .iter()
+                .map(|column| &column.path)
+                .map(|path| quote!(item.#path))
+                .collect(),
+        );
+        // the inlined fields are only laid out as the id when the key struct is
+        // `repr(C)`, checked by `cargo check` or, for generic structs, once
+        // `borrow` is used
+        let flattened = columns.len() != id_field_type.len();
+        let check_layout = flattened.then(|| {
+            let offsets = columns.iter().map(|column| {
+                let name = &column.field.ident;
+                let path = &column.path;
+                let ty = &column.field.ty;
+                quote! {
+                    let _: fn(&#ident #ty_generics) -> &#ty = |item| &item.#path;
+                    assert!(
+                        core::mem::offset_of!(#ident #ty_generics, #path)
+                            == core::mem::offset_of!(#id_ident #id_ty_generics, #name),
+                        "the fields of the `flatten` key struct are not laid out as the id, declare it `#[repr(C)]` with the listed fields in order",
+                    );
+                }
+            });
+            let check_const = input.generics.params.is_empty().then(|| {
+                quote! { const _: () = #ident::__ID_LAYOUT; }
+            });
+            quote! {
+                impl #impl_generics #ident #ty_generics #where_clause {
+                    const __ID_LAYOUT: () = {
+                        #(#offsets)*
+                        assert!(
+                            core::mem::size_of::<#id_ident #id_ty_generics>()
+                                <= core::mem::size_of::<Self>()
+                        );
+                    };
+                }
+                #check_const
+            }
+        });
+        let use_layout = flattened.then(|| quote! { let () = Self::__ID_LAYOUT; });
+        (
+            quote! {
+                #id
+            },
+            quote! {
+                #cached_hash_impl
+                #key_impl
+                #id_struct_impls
+                impl #impl_generics Borrow<#id_ident #id_ty_generics> for #ident #ty_generics #where_clause {
+                    fn borrow(&self) -> &#id_ident #id_ty_generics {
+                        #use_layout
+                        unsafe { &*(self as *const Self as *const #id_ident #id_ty_generics) }
                     }
                 }
+                #check_layout
+                #from_item
             },
         )
     };
+    let readonly_export = if args.readonly.is_some() {
+        let vis = &input.vis;
+        quote! { #vis use #mod_name::#readonly_ident; }
+    } else {
+        quote! {}
+    };
+    let item_impls = item_impls(
+        ident,
+        &input.generics,
+        quote!(#id_ident #id_ty_generics),
+        readonly_ident,
+    );
     Ok(quote! {
         #doc
         #input
         #id_define
+        #readonly_export
         #[doc(hidden)]
+        #[allow(non_snake_case)]
         #[expect(clippy::field_scoped_visibility_modifiers)]
         mod #mod_name {
             #[expect(clippy::wildcard_imports)]
@@ -258,53 +466,589 @@ pub fn readonly(mut input: DeriveInput) -> Result<TokenStream> {
             };
             #readonly
             #id_impls
+            #pointer_borrows
+            #item_impls
+            #prehashed_impl
+            #symbol_checks
+        }
+        #(#attr_errors)*
+    })
+}
+
+/// Comparisons of the item through its id, `Item` and the `Deref` of the
+/// readonly struct, shared by items and views
+fn item_impls(
+    ident: &Ident,
+    generics: &syn::Generics,
+    id: TokenStream,
+    readonly_ident: &Ident,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let item_bounded = |bound: TokenStream| with_bounds(generics, [&id], bound);
+    let item_hash_generics = item_bounded(quote!(Hash));
+    let (item_hash_impl_generics, _, item_hash_where_clause) =
+        item_hash_generics.split_for_impl();
+    let item_eq_generics = item_bounded(quote!(PartialEq));
+    let (item_eq_impl_generics, _, item_eq_where_clause) =
+        item_eq_generics.split_for_impl();
+    let item_total_eq_generics = item_bounded(quote!(Eq));
+    let (item_total_eq_impl_generics, _, item_total_eq_where_clause) =
+        item_total_eq_generics.split_for_impl();
+    let item_ord_generics = item_bounded(quote!(Ord));
+    let (item_ord_impl_generics, _, item_ord_where_clause) =
+        item_ord_generics.split_for_impl();
+    let item_generics = item_bounded(quote!(Hash + Eq));
+    let (item_impl_generics, _, item_where_clause) = item_generics.split_for_impl();
+    quote! {
+        #[doc(hidden)]
+        impl #item_hash_impl_generics Hash for #ident #ty_generics #item_hash_where_clause {
+            #[inline]
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                Borrow::<#id>::borrow(self).hash(state)
+            }
+        }
+        #[doc(hidden)]
+        impl #item_eq_impl_generics PartialEq for #ident #ty_generics #item_eq_where_clause {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                Borrow::<#id>::borrow(self).eq(Borrow::<#id>::borrow(other))
+            }
+        }
+        #[doc(hidden)]
+        impl #item_total_eq_impl_generics Eq for #ident #ty_generics #item_total_eq_where_clause {}
+        #[doc(hidden)]
+        #[allow(clippy::non_canonical_partial_ord_impl)]
+        impl #item_ord_impl_generics PartialOrd for #ident #ty_generics #item_ord_where_clause {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Borrow::<#id>::borrow(self).partial_cmp(Borrow::<#id>::borrow(other))
+            }
+        }
+        #[doc(hidden)]
+        impl #item_ord_impl_generics Ord for #ident #ty_generics #item_ord_where_clause {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                Borrow::<#id>::borrow(self).cmp(Borrow::<#id>::borrow(other))
+            }
+        }
+        impl #item_impl_generics mut_set::Item for #ident #ty_generics #item_where_clause {
+            type Id = #id;
+            type IdReadonlyItem = #readonly_ident #ty_generics;
+            #[expect(invalid_reference_casting)]
+            unsafe fn __unsafe_deref_mut(&self) -> &mut Self::IdReadonlyItem {
+                unsafe { &mut *(self as *const Self as *mut Self::IdReadonlyItem) }
+            }
+        }
+        impl #impl_generics Deref for #readonly_ident #ty_generics #where_clause {
+            type Target = #ident #ty_generics;
+            #[inline]
+            fn deref(&self) -> &Self::Target {
+                unsafe { &*(self as *const Self as *const Self::Target) }
+            }
+        }
+    }
+}
+
+/// Composite id struct, whose impls are shared by items and views
+struct IdStruct<'a> {
+    ident: &'a Ident,
+    generics: &'a syn::Generics,
+    names: Vec<&'a Ident>,
+    types: Vec<&'a Type>,
+    /// Types bounded by the `Hash`/`Eq`/`Ord` impls, the fields compared
+    /// through functions are left to the declared bounds
+    bounded: Vec<&'a Type>,
+    /// Bodies of `hash`, `eq` (joined by `&&`) and `cmp` (chained)
+    hash: TokenStream,
+    eq: Vec<TokenStream>,
+    cmp: Vec<TokenStream>,
+    /// Hidden `__hash` field in front, computed by `new`
+    cache_hash: bool,
+}
+
+impl IdStruct<'_> {
+    /// `new` unless the hash is cached, the comparisons, `Debug`, `Display`,
+    /// `Clone` and the conversions from and to tuples
+    fn impls(&self) -> TokenStream {
+        let Self { ident, names, types, hash, eq, cmp, .. } = self;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let bounded =
+            |bound: TokenStream| with_bounds(self.generics, &self.bounded, bound);
+        let hash_generics = bounded(quote!(Hash));
+        let (hash_impl_generics, _, hash_where_clause) = hash_generics.split_for_impl();
+        let eq_generics = bounded(quote!(PartialEq));
+        let (eq_impl_generics, _, eq_where_clause) = eq_generics.split_for_impl();
+        let total_eq_generics = bounded(quote!(Eq));
+        let (total_eq_impl_generics, _, total_eq_where_clause) =
+            total_eq_generics.split_for_impl();
+        let ord_generics = bounded(quote!(Ord));
+        let (ord_impl_generics, _, ord_where_clause) = ord_generics.split_for_impl();
+        let debug_generics = with_bounds(self.generics, types, quote!(core::fmt::Debug));
+        let (debug_impl_generics, _, debug_where_clause) =
+            debug_generics.split_for_impl();
+        let clone_generics = with_bounds(self.generics, types, quote!(Clone));
+        let (clone_impl_generics, _, clone_where_clause) =
+            clone_generics.split_for_impl();
+        let display = format!("({})", vec!["{:?}"; names.len()].join(", "));
+        let hash_clone = self.cache_hash.then(|| quote! { __hash: self.__hash, });
+        // `new` hashes the fields with `cache_hash`
+        let (new, from_tuple_impl_generics, from_tuple_where_clause) = if self.cache_hash
+        {
+            (None, &hash_impl_generics, hash_where_clause)
+        } else {
+            let new = quote! {
+                impl #impl_generics #ident #ty_generics #where_clause {
+                    #[inline]
+                    pub fn new(#(#names: #types),*) -> Self {
+                        Self { #(#names),* }
+                    }
+                }
+            };
+            (Some(new), &impl_generics, where_clause)
+        };
+        // `(T,)` would be an uncovered foreign type for the orphan rules
+        let into_tuple = self.generics.type_params().next().is_none().then(|| {
+            quote! {
+                impl #impl_generics From<#ident #ty_generics> for (#(#types,)*) #where_clause {
+                    #[inline]
+                    fn from(id: #ident #ty_generics) -> Self {
+                        (#(id.#names,)*)
+                    }
+                }
+            }
+        });
+        quote! {
+            #new
             #[doc(hidden)]
-            impl #impl_generics Hash for #ident #ty_generics #where_clause {
+            impl #hash_impl_generics Hash for #ident #ty_generics #hash_where_clause {
                 #[inline]
                 fn hash<H: Hasher>(&self, state: &mut H) {
-                    self.id().hash(state)
+                    #hash
                 }
             }
             #[doc(hidden)]
-            impl #impl_generics PartialEq for #ident #ty_generics #where_clause {
+            impl #eq_impl_generics PartialEq for #ident #ty_generics #eq_where_clause {
                 #[inline]
                 fn eq(&self, other: &Self) -> bool {
-                    self.id().eq(other.id())
+                    #(#eq)&&*
                 }
             }
             #[doc(hidden)]
-            impl #impl_generics Eq for #ident #ty_generics #where_clause {}
+            impl #total_eq_impl_generics Eq for #ident #ty_generics #total_eq_where_clause {}
             #[doc(hidden)]
-            #[allow(clippy::non_canonical_partial_ord_impl)]
-            impl #impl_generics PartialOrd for #ident #ty_generics #where_clause {
-                fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-                    self.id().partial_cmp(other.id())
+            impl #ord_impl_generics PartialOrd for #ident #ty_generics #ord_where_clause {
+                #[inline]
+                fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                    Some(Ord::cmp(self, other))
                 }
             }
             #[doc(hidden)]
-            impl #impl_generics Ord for #ident #ty_generics #where_clause {
-                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                    self.id().cmp(other.id())
+            impl #ord_impl_generics Ord for #ident #ty_generics #ord_where_clause {
+                fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                    core::cmp::Ordering::Equal #(.then_with(|| #cmp))*
+                }
+            }
+            impl #debug_impl_generics core::fmt::Debug for #ident #ty_generics
+            #debug_where_clause
+            {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.debug_struct(stringify!(#ident))
+                        #(.field(stringify!(#names), &self.#names))*
+                        .finish()
+                }
+            }
+            /// Formatted like the tuple of id fields, e.g. `(2, 4.2, None)`
+            impl #debug_impl_generics core::fmt::Display for #ident #ty_generics
+            #debug_where_clause
+            {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    write!(f, #display, #(self.#names),*)
                 }
             }
-            impl #impl_generics mut_set::Item for #ident #ty_generics #where_clause {
-                type Id = #id_ident;
-                type IdReadonlyItem = #readonly_ident #ty_generics;
-                #[expect(invalid_reference_casting)]
-                unsafe fn __unsafe_deref_mut(&self) -> &mut Self::IdReadonlyItem {
-                    unsafe { &mut *(self as *const Self as *mut Self::IdReadonlyItem) }
+            impl #clone_impl_generics Clone for #ident #ty_generics
+            #clone_where_clause
+            {
+                #[inline]
+                fn clone(&self) -> Self {
+                    Self { #hash_clone #(#names: self.#names.clone()),* }
                 }
             }
-            impl #impl_generics Deref for #readonly_ident #ty_generics #where_clause {
-                type Target = #ident #ty_generics;
+            impl #from_tuple_impl_generics From<(#(#types,)*)> for #ident #ty_generics #from_tuple_where_clause {
                 #[inline]
-                fn deref(&self) -> &Self::Target {
-                    unsafe { &*(self as *const Self as *const Self::Target) }
+                fn from((#(#names,)*): (#(#types,)*)) -> Self {
+                    Self::new(#(#names),*)
                 }
             }
+            #into_tuple
         }
-        #(#attr_errors)*
-    })
+    }
+
+    /// `From<&#item>` cloning the fields out of `paths`, which read `item`
+    fn ref_conversion(
+        &self,
+        item: TokenStream,
+        item_generics: &syn::Generics,
+        paths: Vec<TokenStream>,
+    ) -> TokenStream {
+        let Self { ident, names, .. } = self;
+        let (_, ty_generics, _) = self.generics.split_for_impl();
+        let mut ref_generics = with_bounds(item_generics, &self.types, quote!(Clone));
+        ref_generics.params.insert(0, parse_quote!('__item));
+        let (ref_impl_generics, _, ref_where_clause) = ref_generics.split_for_impl();
+        let hash_from_item = self.cache_hash.then(|| quote! { __hash: item.__hash, });
+        quote! {
+            impl #ref_impl_generics From<&'__item #item> for #ident #ty_generics
+            #ref_where_clause
+            {
+                #[inline]
+                fn from(item: &'__item #item) -> Self {
+                    Self { #hash_from_item #(#names: #paths.clone()),* }
+                }
+            }
+        }
+    }
+}
+
+/// `generics` with `for<'__id> #ty: #bound` for each of `types`, higher-ranked
+/// so that the bounds on concrete types are not rejected as trivial
+fn with_bounds<T: ToTokens>(
+    generics: &syn::Generics,
+    types: impl IntoIterator<Item = T>,
+    bound: TokenStream,
+) -> syn::Generics {
+    let mut generics = generics.clone();
+    generics.make_where_clause().predicates.extend(
+        types
+            .into_iter()
+            .map(|t| -> syn::WherePredicate { parse_quote!(for<'__id> #t: #bound) }),
+    );
+    generics
+}
+
+/// `Borrow` of the id for `Box<#item>`, and for `Rc`/`Arc` when the id is the
+/// generated struct, as the orphan rules reject them for a foreign id type
+fn pointer_borrows(
+    generics: &syn::Generics,
+    item: TokenStream,
+    id: TokenStream,
+    composite: bool,
+) -> TokenStream {
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let pointers: &[&str] = if composite { &["Box", "Rc", "Arc"] } else { &["Box"] };
+    let pointers = pointers.iter().map(|p| Ident::new(p, Span::call_site()));
+    quote! {
+        #(
+            impl #impl_generics Borrow<#id> for mut_set::__private::#pointers<#item> #where_clause {
+                #[inline]
+                fn borrow(&self) -> &#id {
+                    Borrow::<#id>::borrow(&**self)
+                }
+            }
+        )*
+    }
+}
+
+/// `generics` without bounds, which are not checked on type aliases
+fn alias_generics(generics: &syn::Generics) -> syn::Generics {
+    let mut generics = generics.clone();
+    generics.where_clause = None;
+    for param in &mut generics.params {
+        match param {
+            syn::GenericParam::Lifetime(param) => param.bounds.clear(),
+            syn::GenericParam::Type(param) => {
+                param.colon_token = None;
+                param.bounds.clear();
+                param.eq_token = None;
+                param.default = None;
+            }
+            syn::GenericParam::Const(_) => {}
+        }
+    }
+    generics
+}
+
+/// Parameters of `generics` used by `types`, with the bounds that only mention them
+fn used_generics<'a>(
+    generics: &syn::Generics,
+    types: impl IntoIterator<Item = &'a Type>,
+) -> syn::Generics {
+    let mut used = Mentions::default();
+    for ty in types {
+        used.visit_type(ty);
+    }
+    let param_name = |param: &syn::GenericParam| match param {
+        syn::GenericParam::Lifetime(param) => param.lifetime.to_string(),
+        syn::GenericParam::Type(param) => param.ident.to_string(),
+        syn::GenericParam::Const(param) => param.ident.to_string(),
+    };
+    let params: Vec<_> = generics.params.iter().map(param_name).collect();
+    let used: Vec<_> = params.iter().filter(|p| used.0.contains(p)).cloned().collect();
+    // whether `node` mentions only used parameters
+    let only_used = |visit: &dyn Fn(&mut Mentions)| {
+        let mut mentions = Mentions::default();
+        visit(&mut mentions);
+        mentions.0.iter().all(|m| !params.contains(m) || used.contains(m))
+    };
+    let mut subset = generics.clone();
+    subset.params = generics
+        .params
+        .iter()
+        .filter(|param| used.contains(&param_name(param)))
+        .cloned()
+        .map(|mut param| {
+            match &mut param {
+                syn::GenericParam::Lifetime(param) => {
+                    param.bounds = core::mem::take(&mut param.bounds)
+                        .into_iter()
+                        .filter(|b| only_used(&|m| m.visit_lifetime(b)))
+                        .collect();
+                }
+                syn::GenericParam::Type(param) => {
+                    param.bounds = core::mem::take(&mut param.bounds)
+                        .into_iter()
+                        .filter(|b| only_used(&|m| m.visit_type_param_bound(b)))
+                        .collect();
+                    if param
+                        .default
+                        .as_ref()
+                        .is_some_and(|d| !only_used(&|m| m.visit_type(d)))
+                    {
+                        param.eq_token = None;
+                        param.default = None;
+                    }
+                }
+                syn::GenericParam::Const(_) => {}
+            }
+            param
+        })
+        .collect();
+    if let Some(where_clause) = &mut subset.where_clause {
+        where_clause.predicates = core::mem::take(&mut where_clause.predicates)
+            .into_iter()
+            .filter(|p| only_used(&|m| m.visit_where_predicate(p)))
+            .collect();
+    }
+    subset
+}
+
+/// Lifetimes and first path segments, which include the generic parameters used
+#[derive(Default)]
+struct Mentions(Vec<String>);
+
+impl<'ast> Visit<'ast> for Mentions {
+    fn visit_lifetime(&mut self, lifetime: &'ast syn::Lifetime) {
+        self.0.push(lifetime.to_string());
+    }
+    fn visit_path(&mut self, path: &'ast Path) {
+        if path.leading_colon.is_none()
+            && let Some(first) = path.segments.first()
+        {
+            self.0.push(first.ident.to_string());
+        }
+        visit::visit_path(self, path);
+    }
+}
+
+/// The items are structs with named fields
+fn check_named_struct(input: &DeriveInput) -> Result<()> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) if !fields.named.is_empty() => {}
+            Fields::Named(_) | Fields::Unit => {
+                return Err(Error::new(
+                    input.ident.span(),
+                    "input must be a struct with named fields",
+                ));
+            }
+            Fields::Unnamed(fields) => {
+                return Err(Error::new_spanned(
+                    fields,
+                    "input must be a struct with named fields",
+                ));
+            }
+        },
+        Data::Enum(data) => {
+            return Err(Error::new_spanned(data.enum_token, "input must be a struct"));
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(data.union_token, "input must be a struct"));
+        }
+    }
+    Ok(())
+}
+
+fn check_compute_id(
+    args: &ItemArgs,
+    id_field_type: &[(Field, BorrowType)],
+) -> Result<()> {
+    match &args.compute_id {
+        Some(compute_id) => {
+            if let Some(cache_hash) = args.cache_hash {
+                return Err(Error::new(
+                    cache_hash,
+                    "`cache_hash` can not be combined with `compute_id`",
+                ));
+            }
+            if let Some((field, _)) =
+                id_field_type.iter().find(|(_, t)| t.source.is_none())
+            {
+                return Err(Error::new_spanned(
+                    &field.ident,
+                    "the id is hashed by `compute_id`, mark the fields it reads `#[id(source)]`",
+                ));
+            }
+            if matches!(compute_id, Expr::Closure(closure) if closure.inputs.len() != 1) {
+                return Err(Error::new_spanned(
+                    compute_id,
+                    "`compute_id` takes the id as its only argument",
+                ));
+            }
+        }
+        None => {
+            if let Some(source) =
+                id_field_type.iter().find_map(|(_, t)| t.source.as_ref())
+            {
+                return Err(Error::new_spanned(
+                    source,
+                    "`source` needs `#[item(compute_id = ..)]`, which reads the field",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A field of `XId`, read from the item by `path`
+struct IdColumn {
+    field: Field,
+    borrow_type: BorrowType,
+    path: TokenStream,
+}
+
+/// The fields of `XId`, the listed fields of a `flatten` key struct are
+/// inlined next to the other id fields
+fn id_columns(id_field_type: &[(Field, BorrowType)]) -> Vec<IdColumn> {
+    let mut columns = Vec::new();
+    for (field, borrow_type) in id_field_type {
+        let name = field.ident.as_ref().unwrap();
+        if borrow_type.flatten_fields.is_empty() {
+            let mut field = field.clone();
+            field.attrs.clear();
+            columns.push(IdColumn {
+                field,
+                borrow_type: borrow_type.clone(),
+                path: quote!(#name),
+            });
+            continue;
+        }
+        for inner in &borrow_type.flatten_fields {
+            let inner_name = &inner.ident;
+            columns.push(IdColumn {
+                field: Field {
+                    attrs: Vec::new(),
+                    vis: field.vis.clone(),
+                    ..inner.clone()
+                },
+                borrow_type: BorrowType::default(),
+                path: quote!(#name.#inner_name),
+            });
+        }
+    }
+    columns
+}
+
+fn check_flatten(args: &ItemArgs, id_field_type: &[(Field, BorrowType)]) -> Result<()> {
+    let single = id_field_type.len() == 1;
+    for (_, borrow_type) in id_field_type {
+        let Some(flatten) = &borrow_type.flatten else {
+            continue;
+        };
+        if args.cache_hash.is_some() {
+            return Err(Error::new_spanned(
+                flatten,
+                "`flatten` can not be combined with `cache_hash`",
+            ));
+        }
+        if args.compute_id.is_some() {
+            return Err(Error::new_spanned(
+                flatten,
+                "`flatten` can not be combined with `compute_id`",
+            ));
+        }
+        if single && !borrow_type.flatten_fields.is_empty() {
+            return Err(Error::new_spanned(
+                flatten,
+                "a single `flatten` field is the id itself, drop the field list",
+            ));
+        }
+        if !single && borrow_type.flatten_fields.is_empty() {
+            return Err(Error::new_spanned(
+                flatten,
+                "`flatten` next to other id fields inlines the fields of the key struct, list them, e.g. `flatten(lib: String, name: String)`",
+            ));
+        }
+        if !single && let Some(borrow) = &borrow_type.borrow_type {
+            return Err(Error::new_spanned(
+                borrow,
+                "`borrow` needs a single `#[id]` field",
+            ));
+        }
+    }
+    let mut names = Vec::new();
+    for column in id_columns(id_field_type) {
+        let name = column.field.ident.unwrap();
+        if names.contains(&name) {
+            return Err(Error::new(
+                name.span(),
+                format!(
+                    "duplicate id field `{name}`, the inlined fields of `flatten` are fields of the id"
+                ),
+            ));
+        }
+        names.push(name);
+    }
+    Ok(())
+}
+
+fn check_cache_hash(
+    args: &ItemArgs,
+    id_field_type: &[(Field, BorrowType)],
+) -> Result<()> {
+    if let Some(derive_id) = args.derive_id_span {
+        return Err(Error::new(
+            derive_id,
+            "`derive_id` can not be combined with `cache_hash`, the hash is computed by `new`",
+        ));
+    }
+    for (_, borrow_type) in id_field_type {
+        if let Some(borrow_type) = &borrow_type.borrow_type {
+            return Err(Error::new_spanned(
+                borrow_type,
+                "`borrow` can not be combined with `cache_hash`, the borrowed value would hash differently",
+            ));
+        }
+        if let Some(prehashed) = &borrow_type.prehashed {
+            return Err(Error::new_spanned(
+                prehashed,
+                "`prehashed` can not be combined with `cache_hash`",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Strip the `#[id]` helper attributes and the stacked `#[item]`s, so that
+/// when expansion fails only the actual error is reported
+pub fn strip_id_attrs(input: &mut DeriveInput) {
+    input.attrs.retain(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_none_or(|segment| segment.ident != "item")
+    });
+    if let Data::Struct(data) = &mut input.data {
+        for field in data.fields.iter_mut() {
+            field.attrs.retain(|attr| !attr.path().is_ident("id"));
+        }
+    }
 }
 
 // TODO
@@ -352,28 +1096,37 @@ fn rearange_by_id(
     let mut id_field_type = Vec::new();
     let mut id_fields = Punctuated::new();
     let mut other_fields = Punctuated::new();
-    'L: for field in fields.iter() {
+    for field in fields.iter() {
         let mut field = field.clone();
-        for (j, attr) in field.attrs.iter().enumerate() {
-            if attr.path().is_ident("id") {
-                let borrow_type = match attr.meta {
-                    syn::Meta::Path(_) => BorrowType::default(),
-                    syn::Meta::List(_) => match attr.parse_args_with(BorrowType::parse) {
-                        Ok(t) => t,
-                        Err(e) => {
-                            errors.push(e);
-                            BorrowType::default()
-                        }
-                    },
-                    syn::Meta::NameValue(_) => todo!(),
-                };
-                field.attrs.remove(j);
-                id_field_type.push((field.clone(), borrow_type));
-                id_fields.push(field.clone());
-                continue 'L;
+        let mut borrow_type = None;
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("id")) {
+            let parsed = match &attr.meta {
+                syn::Meta::Path(_) => Ok(BorrowType::default()),
+                syn::Meta::List(_) => attr.parse_args_with(BorrowType::parse),
+                syn::Meta::NameValue(meta) => Err(Error::new_spanned(
+                    meta,
+                    "expected `#[id]` or `#[id(...)]`, found `#[id = ...]`",
+                )),
+            };
+            if borrow_type.is_some() {
+                errors.push(Error::new_spanned(
+                    attr,
+                    "duplicate `#[id]`, merge the options into one `#[id(...)]`",
+                ));
+                continue;
             }
+            borrow_type = Some(parsed.unwrap_or_else(|e| {
+                errors.push(e);
+                BorrowType::default()
+            }));
+        }
+        field.attrs.retain(|attr| !attr.path().is_ident("id"));
+        if let Some(borrow_type) = borrow_type {
+            id_field_type.push((field.clone(), borrow_type));
+            id_fields.push(field);
+        } else {
+            other_fields.push(field);
         }
-        other_fields.push(field.clone());
     }
     fields.clear();
     fields.extend(id_fields);
@@ -381,6 +1134,19 @@ fn rearange_by_id(
     id_field_type
 }
 
+/// Whether `ty` names `mut_set::intern::Symbol`, by any path
+fn is_symbol(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) => {
+            ty.qself.is_none()
+                && ty.path.segments.last().is_some_and(|segment| {
+                    segment.ident == "Symbol" && segment.arguments.is_none()
+                })
+        }
+        _ => false,
+    }
+}
+
 struct ReplaceSelf<'a> {
     with: &'a Path,
 }
@@ -403,16 +1169,61 @@ impl VisitMut for ReplaceSelf<'_> {
     }
 }
 
-fn to_snake_case(s: &str) -> String {
-    let mut snake_case = String::new();
-    let chars: Vec<char> = s.chars().collect();
-    for (i, &c) in chars.iter().enumerate() {
-        if c.is_uppercase() && i != 0 {
-            snake_case.push('_');
+/// Rank of a visibility, `None` for `pub(in path)`, which is not compared
+fn vis_rank(vis: &Visibility) -> Option<u8> {
+    match vis {
+        Visibility::Public(_) => Some(3),
+        Visibility::Restricted(restricted) if restricted.in_token.is_none() => {
+            if restricted.path.is_ident("crate") {
+                Some(2)
+            } else if restricted.path.is_ident("super") {
+                Some(1)
+            } else {
+                Some(0)
+            }
         }
-        snake_case.push(c.to_lowercase().next().unwrap());
+        Visibility::Restricted(_) => None,
+        Visibility::Inherited => Some(0),
+    }
+}
+
+/// A generated id struct is named by the `Item` and `Borrow` impls of the
+/// item, so it is at least as visible
+fn check_id_vis(args: &ItemArgs, item_vis: &Visibility, item: &Ident) -> Result<()> {
+    let Some(id_vis) = &args.id_vis else {
+        return Ok(());
+    };
+    if let (Some(id_rank), Some(item_rank)) = (vis_rank(id_vis), vis_rank(item_vis))
+        && id_rank < item_rank
+    {
+        return Err(Error::new_spanned(
+            id_vis,
+            format!(
+                "`id_vis` is less visible than `{item}`, which names the id in its `Item` and `Borrow` impls"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn check_name_collision(item: &Ident, id: &Ident, readonly: &Ident) -> Result<()> {
+    let collision = |ident: &Ident, other: &Ident, what: &str| {
+        Error::new(
+            ident.span(),
+            format!(
+                "the generated {what} `{ident}` collides with `{other}`, rename it with `#[item(...)]`"
+            ),
+        )
+    };
+    if id == item {
+        Err(collision(id, item, "id type"))
+    } else if readonly == item {
+        Err(collision(readonly, item, "readonly type"))
+    } else if id == readonly {
+        Err(collision(readonly, id, "readonly type"))
+    } else {
+        Ok(())
     }
-    snake_case
 }
 fn to_super(vis: &Visibility) -> Visibility {
     match vis {
@@ -478,47 +1289,460 @@ fn rearange_test() {
     };
     let mut errors = Vec::new();
     let id_field_type = rearange_by_id(&mut input, &mut errors);
-    println!("{}", input.into_token_stream().to_string());
+    println!("{}", input.into_token_stream());
     for (field, _type) in id_field_type {
         if let Some(borrow_type) = _type.borrow_type {
             println!("{}", borrow_type.to_token_stream());
         }
         if let Some(expr) = _type.into_hash_ord_fn {
-            println!("{}", expr.to_token_stream().to_string());
+            println!("{}", expr.to_token_stream());
         }
-        println!("{}", field.to_token_stream().to_string());
+        println!("{}", field.to_token_stream());
     }
 }
 
+/// Options of `#[item(...)]`
+#[derive(Default)]
+pub struct ItemArgs {
+    /// Name of `XId`
+    id: Option<Ident>,
+    /// Name of `IdReadonlyX`, which is re-exported when given
+    readonly: Option<Ident>,
+    /// Visibility of `XId`, at least as visible as the item
+    id_vis: Option<Visibility>,
+    /// Extra derives on `XId`
+    derive_id: Vec<Path>,
+    derive_id_span: Option<Span>,
+    /// Store the hash of the id fields in the item and the id
+    cache_hash: Option<Span>,
+    /// Projection of `XId`, called as `f(&id)`, that hashes, compares and
+    /// orders the id in place of its fields
+    compute_id: Option<Expr>,
+    /// Newtype implementing `Item` keyed by `view_ids`, the struct itself is
+    /// left as it is
+    view: Option<Ident>,
+    view_ids: Vec<Ident>,
+    view_ids_span: Option<Span>,
+}
+
+impl ItemArgs {
+    pub fn is_view(&self) -> bool {
+        self.view.is_some()
+    }
+}
+
+const ITEM_ARGS: &str =
+    "`id`, `readonly`, `id_vis`, `derive_id`, `cache_hash`, `compute_id`, `view`";
+const ALWAYS_IMPLEMENTED: [&str; 7] =
+    ["Hash", "PartialEq", "Eq", "PartialOrd", "Ord", "Debug", "Clone"];
+
+impl Parse for ItemArgs {
+    fn parse(input: syn::parse::ParseStream) -> Result<Self> {
+        let mut args = Self::default();
+        let mut keys = Vec::new();
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key == "id" && input.peek(token::Paren) {
+                if args.view_ids_span.is_some() {
+                    return Err(Error::new(key.span(), "duplicate option `id(..)`"));
+                }
+                let content;
+                syn::parenthesized!(content in input);
+                args.view_ids
+                    .extend(content.parse_terminated(Ident::parse, Token![,])?);
+                args.view_ids_span = Some(key.span());
+                if !input.is_empty() {
+                    let _: Token![,] = input.parse()?;
+                }
+                continue;
+            }
+            check_duplicate(&mut keys, &key)?;
+            match key.to_string().as_str() {
+                "id" => {
+                    let _: Token![=] = input.parse()?;
+                    args.id = Some(parse_ident_str(input)?);
+                }
+                "readonly" => {
+                    let _: Token![=] = input.parse()?;
+                    args.readonly = Some(parse_ident_str(input)?);
+                }
+                "id_vis" => {
+                    let _: Token![=] = input.parse()?;
+                    args.id_vis = Some(input.parse()?);
+                }
+                "derive_id" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    for path in
+                        content.parse_terminated(Path::parse_mod_style, Token![,])?
+                    {
+                        if ALWAYS_IMPLEMENTED.iter().any(|name| path.is_ident(name)) {
+                            return Err(Error::new_spanned(
+                                &path,
+                                format!(
+                                    "`{}` is always implemented for the id",
+                                    path.to_token_stream()
+                                ),
+                            ));
+                        }
+                        args.derive_id.push(path);
+                    }
+                    args.derive_id_span = Some(key.span());
+                }
+                "cache_hash" => args.cache_hash = Some(key.span()),
+                "compute_id" => {
+                    let _: Token![=] = input.parse()?;
+                    args.compute_id = Some(input.parse()?);
+                }
+                "view" => {
+                    let _: Token![=] = input.parse()?;
+                    args.view = Some(parse_ident_str(input)?);
+                }
+                _ => {
+                    return Err(Error::new(
+                        key.span(),
+                        format!("unknown option `{key}`, expected one of {ITEM_ARGS}"),
+                    ));
+                }
+            }
+            if !input.is_empty() {
+                let _: Token![,] = input.parse()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+fn check_duplicate(keys: &mut Vec<Ident>, key: &Ident) -> Result<()> {
+    if keys.contains(key) {
+        return Err(Error::new(key.span(), format!("duplicate option `{key}`")));
+    }
+    keys.push(key.clone());
+    Ok(())
+}
+
+fn parse_ident_str(input: syn::parse::ParseStream) -> Result<Ident> {
+    let lit: syn::LitStr = input.parse()?;
+    lit.parse().map_err(|_| {
+        Error::new(lit.span(), format!("`{}` is not a valid identifier", lit.value()))
+    })
+}
+
+#[test]
+fn item_args_test() {
+    let args: ItemArgs = parse_quote!(
+        id = "RouteKey",
+        readonly = "RouteView",
+        id_vis = pub(crate),
+        derive_id(Default, serde::Serialize)
+    );
+    assert_eq!(args.id.unwrap(), "RouteKey");
+    assert_eq!(args.readonly.unwrap(), "RouteView");
+    assert_eq!(args.id_vis.to_token_stream().to_string(), "pub (crate)");
+    assert_eq!(args.derive_id.len(), 2);
+    assert!(args.cache_hash.is_none());
+    assert!(syn::parse_str::<ItemArgs>("cache_hash").unwrap().cache_hash.is_some());
+    assert!(syn::parse_str::<ItemArgs>("name = \"Foo\"").is_err());
+    assert!(syn::parse_str::<ItemArgs>("derive_id(Hash)").is_err());
+    assert!(syn::parse_str::<ItemArgs>("id = \"Foo Bar\"").is_err());
+    let args: ItemArgs = parse_quote!(view = "ByPos", id(x, y), id = "Pos");
+    assert_eq!(args.view.unwrap(), "ByPos");
+    assert_eq!(args.view_ids.len(), 2);
+    assert_eq!(args.id.unwrap(), "Pos");
+    assert!(syn::parse_str::<ItemArgs>("id(x), id(y)").is_err());
+}
+
 #[derive(Clone, Default)]
 struct BorrowType {
     borrow_type: Option<Type>,
     into_hash_ord_fn: Option<Expr>,
+    hash_with: Option<Expr>,
+    eq_with: Option<Expr>,
+    cmp_with: Option<Expr>,
+    /// Hash with `NoHashBuildHasher`, through `mut_set::Prehashed`
+    prehashed: Option<Ident>,
+    /// Declared as `mut_set::intern::Symbol`, looked up by `mut_set::intern::Str`
+    intern: Option<Ident>,
+    /// The key struct is the id, `XId` is an alias of it
+    flatten: Option<Ident>,
+    /// Fields of the key struct, inlined into `XId` next to other id fields
+    flatten_fields: Vec<Field>,
+    /// Read by `#[item(compute_id = ..)]`, part of `XId` but not hashed itself
+    source: Option<Ident>,
+}
+
+impl BorrowType {
+    /// Whether the field can be used as the id type directly
+    fn is_plain(&self) -> bool {
+        self.into_hash_ord_fn.is_none()
+            && self.hash_with.is_none()
+            && self.eq_with.is_none()
+            && self.cmp_with.is_none()
+    }
+    /// Type the item can be looked up by, besides the id
+    fn extra_borrow(&self) -> Option<Type> {
+        if self.intern.is_some() {
+            Some(parse_quote!(mut_set::intern::Str))
+        } else {
+            self.borrow_type.clone()
+        }
+    }
+    fn hash(&self, i: &Ident) -> TokenStream {
+        if let Some(into_hash_ord_fn) = &self.into_hash_ord_fn {
+            quote! { Hash::hash(&#into_hash_ord_fn(&self.#i), state); }
+        } else if let Some(hash_with) = &self.hash_with {
+            quote! { #hash_with(&self.#i, state); }
+        } else {
+            quote! { Hash::hash(&self.#i, state); }
+        }
+    }
+    fn eq(&self, i: &Ident) -> TokenStream {
+        if let Some(into_hash_ord_fn) = &self.into_hash_ord_fn {
+            quote! { #into_hash_ord_fn(&self.#i) == #into_hash_ord_fn(&other.#i) }
+        } else if self.hash_with.is_some() || self.eq_with.is_some() {
+            let eq = self.eq_with.as_ref().map_or_else(
+                || quote! { self.#i == other.#i },
+                |eq_with| quote! { #eq_with(&self.#i, &other.#i) },
+            );
+            let hash = self.hash_with.as_ref().map_or_else(
+                || quote! { Hash::hash(value, state) },
+                |hash_with| quote! { #hash_with(value, state) },
+            );
+            quote! {({
+                let eq = #eq;
+                #[cfg(debug_assertions)]
+                if eq {
+                    mut_set::__private::check_eq_hash(
+                        stringify!(#i),
+                        &self.#i,
+                        &other.#i,
+                        |value, state| #hash,
+                    );
+                }
+                eq
+            })}
+        } else {
+            quote! { self.#i == other.#i }
+        }
+    }
+    fn cmp(&self, i: &Ident) -> TokenStream {
+        if let Some(into_hash_ord_fn) = &self.into_hash_ord_fn {
+            quote! { Ord::cmp(&#into_hash_ord_fn(&self.#i), &#into_hash_ord_fn(&other.#i)) }
+        } else if let Some(cmp_with) = &self.cmp_with {
+            let eq = self.eq_with.as_ref().map_or_else(
+                || quote! { self.#i == other.#i },
+                |eq_with| quote! { #eq_with(&self.#i, &other.#i) },
+            );
+            quote! {{
+                let ord: core::cmp::Ordering = #cmp_with(&self.#i, &other.#i);
+                debug_assert_eq!(
+                    ord.is_eq(),
+                    #eq,
+                    "`{}`: `cmp_with` is inconsistent with equality",
+                    stringify!(#i),
+                );
+                ord
+            }}
+        } else {
+            quote! { Ord::cmp(&self.#i, &other.#i) }
+        }
+    }
 }
 
+const ID_ARGS: [&str; 9] = [
+    "borrow",
+    "into_hash_ord_fn",
+    "hash_with",
+    "eq_with",
+    "cmp_with",
+    "prehashed",
+    "intern",
+    "flatten",
+    "source",
+];
+
 impl syn::parse::Parse for BorrowType {
     fn parse(input: syn::parse::ParseStream) -> Result<Self> {
-        let mut borrow_type = None;
-        let mut into_hash_ord_fn = None;
+        let mut borrow_type = Self::default();
+        let mut keys = Vec::new();
         while !input.is_empty() {
             let ident: syn::Ident = input.parse()?;
+            check_duplicate(&mut keys, &ident)?;
+            if !ID_ARGS.contains(&ident.to_string().as_str()) {
+                return Err(Error::new(
+                    ident.span(),
+                    format!(
+                        "unknown option `{ident}`, expected one of {}",
+                        ID_ARGS.map(|arg| format!("`{arg}`")).join(", ")
+                    ),
+                ));
+            }
+            if ["prehashed", "intern", "flatten", "source"]
+                .iter()
+                .any(|flag| ident == flag)
+            {
+                if ident == "prehashed" {
+                    borrow_type.prehashed = Some(ident);
+                } else if ident == "intern" {
+                    borrow_type.intern = Some(ident);
+                } else if ident == "flatten" {
+                    // `flatten(lib: String, name: String)`, the fields of the key
+                    // struct, which the macro can not see
+                    if input.peek(token::Paren) {
+                        let content;
+                        syn::parenthesized!(content in input);
+                        let fields =
+                            content.parse_terminated(Field::parse_named, Token![,])?;
+                        if fields.is_empty() {
+                            return Err(Error::new(
+                                ident.span(),
+                                "list the inlined fields of the key struct, e.g. `flatten(lib: String, name: String)`",
+                            ));
+                        }
+                        borrow_type.flatten_fields = fields.into_iter().collect();
+                    }
+                    borrow_type.flatten = Some(ident);
+                } else {
+                    borrow_type.source = Some(ident);
+                }
+                if !input.is_empty() {
+                    let _: Token![,] = input.parse()?;
+                }
+                continue;
+            }
             let _: Token![=] = input.parse()?;
             match ident.to_string().as_str() {
                 "borrow" => {
-                    borrow_type = Some(input.parse()?);
+                    borrow_type.borrow_type = Some(input.parse()?);
                 }
                 "into_hash_ord_fn" => {
-                    into_hash_ord_fn = Some(input.parse()?);
+                    borrow_type.into_hash_ord_fn = Some(input.parse()?);
+                }
+                "hash_with" => {
+                    borrow_type.hash_with = Some(input.parse()?);
+                }
+                "eq_with" => {
+                    borrow_type.eq_with = Some(input.parse()?);
                 }
-                _ => {}
+                "cmp_with" => {
+                    borrow_type.cmp_with = Some(input.parse()?);
+                }
+                _ => unreachable!(),
             }
-            if input.peek(Token![,]) {
+            if !input.is_empty() {
                 let _: Token![,] = input.parse()?;
             }
         }
-        Ok(Self { borrow_type, into_hash_ord_fn })
+        borrow_type.check()?;
+        Ok(borrow_type)
+    }
+}
+
+impl BorrowType {
+    fn check(&self) -> Result<()> {
+        if let Some(into_hash_ord_fn) = &self.into_hash_ord_fn
+            && (self.hash_with.is_some()
+                || self.eq_with.is_some()
+                || self.cmp_with.is_some())
+        {
+            return Err(Error::new_spanned(
+                into_hash_ord_fn,
+                "`into_hash_ord_fn` can not be combined with `hash_with`, `eq_with` or `cmp_with`",
+            ));
+        }
+        if let Some(eq_with) = &self.eq_with
+            && self.hash_with.is_none()
+        {
+            return Err(Error::new_spanned(
+                eq_with,
+                "`eq_with` needs `hash_with`, values that are equal must hash equally",
+            ));
+        }
+        if let Some(eq_with) = &self.eq_with
+            && self.cmp_with.is_none()
+        {
+            return Err(Error::new_spanned(
+                eq_with,
+                "`eq_with` needs `cmp_with`, values that are equal must be ordered equal",
+            ));
+        }
+        if let Some(prehashed) = &self.prehashed
+            && !self.is_plain()
+        {
+            return Err(Error::new_spanned(
+                prehashed,
+                "`prehashed` can not be combined with custom hash, equality or ordering",
+            ));
+        }
+        if let Some(intern) = &self.intern
+            && (self.borrow_type.is_some() || !self.is_plain())
+        {
+            return Err(Error::new_spanned(
+                intern,
+                "`intern` can not be combined with `borrow` or custom hash, equality or ordering",
+            ));
+        }
+        if let Some(flatten) = &self.flatten
+            && (self.prehashed.is_some() || self.intern.is_some() || !self.is_plain())
+        {
+            return Err(Error::new_spanned(
+                flatten,
+                "`flatten` can not be combined with `prehashed`, `intern` or custom hash, equality or ordering",
+            ));
+        }
+        if let Some(source) = &self.source
+            && (self.borrow_type.is_some()
+                || self.prehashed.is_some()
+                || self.intern.is_some()
+                || self.flatten.is_some()
+                || !self.is_plain())
+        {
+            return Err(Error::new_spanned(
+                source,
+                "`source` can not be combined with other options, the id is hashed, compared and ordered by `compute_id`",
+            ));
+        }
+        if let Some(borrow_type) = &self.borrow_type
+            && !self.is_plain()
+        {
+            return Err(Error::new_spanned(
+                borrow_type,
+                "`borrow` can not be combined with custom hash, equality or ordering",
+            ));
+        }
+        Ok(())
     }
 }
+
+#[test]
+fn borrow_type_check_test() {
+    let parse = |s: &str| syn::parse_str::<BorrowType>(s).map(|t| t.is_plain());
+    assert!(!parse("hash_with = h, eq_with = e, cmp_with = c").unwrap());
+    assert!(!parse("hash_with = h").unwrap());
+    assert!(parse("borrow = str").unwrap());
+    assert!(parse("eq_with = e").is_err());
+    assert!(parse("hash_with = h, eq_with = e").is_err());
+    assert!(parse("into_hash_ord_fn = f, cmp_with = c").is_err());
+    assert!(parse("borrow = str, hash_with = h").is_err());
+    assert!(parse("borrow = str, borrow = String").is_err());
+    assert!(parse("hash_fn = h").is_err());
+    assert!(parse("prehashed, borrow = u64").unwrap());
+    assert!(parse("prehashed = true").is_err());
+    assert!(parse("prehashed, hash_with = h").is_err());
+    assert!(parse("intern, prehashed").unwrap());
+    assert!(parse("intern, borrow = str").is_err());
+    assert!(parse("intern, into_hash_ord_fn = f").is_err());
+    assert!(parse("flatten, borrow = str").unwrap());
+    assert!(parse("flatten(lib: String, name: String)").unwrap());
+    assert!(parse("flatten()").is_err());
+    assert!(parse("flatten(lib)").is_err());
+    assert!(parse("flatten, intern").is_err());
+    assert!(parse("flatten, cmp_with = c").is_err());
+    assert!(parse("source").unwrap());
+    assert!(parse("source, borrow = str").is_err());
+    assert!(parse("source, hash_with = h").is_err());
+}
+
 #[test]
 fn parser_args_test1() {
     let mut hash_impl_generics: syn::Generics = parse_quote!(<T1,T2>);
@@ -0,0 +1,298 @@
+//! `#[item(view = "ByX", id(..))]`, a newtype keyed by some fields of the struct
+
+use super::*;
+
+/// Newtype of the struct implementing `Item` keyed by `id(..)`. The fields stay
+/// in place, so a composite id is borrowed at the offset of its first field.
+pub fn view(args: ItemArgs, mut input: DeriveInput) -> Result<TokenStream> {
+    let call_site = Span::call_site();
+
+    check_named_struct(&input)?;
+    let view_ident = args.view.clone().unwrap();
+    if args.view_ids.is_empty() {
+        return Err(Error::new(
+            view_ident.span(),
+            "`view` needs its id fields, e.g. `id(x, y)`",
+        ));
+    }
+    if let Some(cache_hash) = args.cache_hash {
+        return Err(Error::new(
+            cache_hash,
+            "`cache_hash` can not be combined with `view`",
+        ));
+    }
+    if let Some(compute_id) = &args.compute_id {
+        return Err(Error::new_spanned(
+            compute_id,
+            "`compute_id` can not be combined with `view`",
+        ));
+    }
+    // a view runs first when it is above, and the fields it borrows in place
+    // would then be moved by `#[item]`
+    if let Some(item) = input.attrs.iter().find(|attr| is_plain_item(attr)) {
+        return Err(Error::new_spanned(
+            item,
+            "`#[item]` reorders the fields, put it above the views of the struct",
+        ));
+    }
+    let reprs = has_defined_repr(&input);
+    if let Some(repr) = reprs.iter().find(|repr| !is_repr_c(repr)) {
+        return Err(Error::new_spanned(
+            repr,
+            "a view borrows its id in place, which needs `#[repr(C)]`",
+        ));
+    }
+    if reprs.is_empty() {
+        input.attrs.push(parse_quote!(#[repr(C)]));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let self_path: Path = parse_quote!(#ident #ty_generics);
+    let mut fields = fields_of_input(&mut input.clone()).clone();
+    for field in fields.iter_mut() {
+        field.attrs.clear();
+        ReplaceSelf::new(&self_path).visit_type_mut(&mut field.ty);
+    }
+    let mut positions: Vec<usize> = Vec::new();
+    for id in &args.view_ids {
+        let Some(position) = fields.iter().position(|f| f.ident.as_ref() == Some(id))
+        else {
+            return Err(Error::new(id.span(), format!("`{ident}` has no field `{id}`")));
+        };
+        if positions.last().is_some_and(|&last| position != last + 1) {
+            return Err(Error::new(
+                id.span(),
+                "the id fields of a view are borrowed in place, list adjacent fields in declaration order",
+            ));
+        }
+        positions.push(position);
+    }
+    let id_fields: Vec<_> = positions.iter().map(|&i| &fields[i]).collect();
+    let id_names: Vec<_> = id_fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let id_types: Vec<_> = id_fields.iter().map(|f| &f.ty).collect();
+
+    let id_ident = args
+        .id
+        .clone()
+        .unwrap_or_else(|| Ident::new(&format!("{view_ident}Id"), view_ident.span()));
+    let readonly_ident = args.readonly.clone().unwrap_or_else(|| {
+        Ident::new(&format!("IdReadonly{view_ident}"), view_ident.span())
+    });
+    if view_ident == *ident {
+        return Err(Error::new(
+            view_ident.span(),
+            format!("the view `{view_ident}` collides with the struct"),
+        ));
+    }
+    check_name_collision(&view_ident, &id_ident, &readonly_ident)?;
+    let mod_name = Ident::new(&format!("__{view_ident}"), call_site);
+
+    let mut readonly = input.clone();
+    readonly.ident = readonly_ident.clone();
+    readonly.attrs.retain(|attr| attr.path().is_ident("repr"));
+    readonly.attrs.insert(0, parse_quote!(#[doc(hidden)]));
+    readonly.vis = to_super(&input.vis);
+    for (i, field) in fields_of_input(&mut readonly).iter_mut().enumerate() {
+        *field = fields[i].clone();
+        field.vis = if positions.contains(&i) {
+            Visibility::Inherited
+        } else {
+            to_super(&field.vis)
+        };
+    }
+
+    let vis = &input.vis;
+    let doc = format!(
+        "[`{ident}`] keyed by `({})`",
+        args.view_ids
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let id_generics = used_generics(&input.generics, id_types.iter().copied());
+    let (_, id_ty_generics, id_where_clause) = id_generics.split_for_impl();
+
+    let (id_define, id_impls) = if let [id_field] = id_fields[..] {
+        if let Some(span) = args.derive_id_span {
+            return Err(Error::new(
+                span,
+                "`derive_id` needs a composite id, a single id field is used as the id type directly",
+            ));
+        }
+        let id_vis = args.id_vis.clone().unwrap_or(parse_quote!(pub));
+        let name = &id_field.ident;
+        let ty = &id_field.ty;
+        let alias_generics = alias_generics(&id_generics);
+        (
+            quote! {
+                #id_vis type #id_ident #alias_generics = #ty;
+            },
+            quote! {
+                impl #impl_generics Borrow<#ty> for #view_ident #ty_generics #where_clause {
+                    fn borrow(&self) -> &#ty {
+                        &self.0.#name
+                    }
+                }
+            },
+        )
+    } else {
+        check_id_vis(&args, &input.vis, &view_ident)?;
+        let id_vis = args.id_vis.clone().unwrap_or_else(|| input.vis.clone());
+        let derive_id = &args.derive_id;
+        let derive_id =
+            (!derive_id.is_empty()).then(|| quote!(#[derive(#(#derive_id),*)]));
+        let first = id_names[0];
+        let id_struct = IdStruct {
+            ident: &id_ident,
+            generics: &id_generics,
+            names: id_names.clone(),
+            types: id_types.clone(),
+            bounded: id_types.clone(),
+            hash: quote! { #(Hash::hash(&self.#id_names, state);)* },
+            eq: id_names.iter().map(|i| quote! { self.#i == other.#i }).collect(),
+            cmp: id_names
+                .iter()
+                .map(|i| quote! { Ord::cmp(&self.#i, &other.#i) })
+                .collect(),
+            cache_hash: false,
+        };
+        let id_struct_impls = id_struct.impls();
+        let from_view = id_struct.ref_conversion(
+            quote!(#view_ident #ty_generics),
+            &input.generics,
+            id_names.iter().map(|name| quote!(item.0.#name)).collect(),
+        );
+        // evaluated by `cargo check`, the offsets of generic structs are only
+        // checked once `borrow` is used
+        let check_offset = input.generics.params.is_empty().then(|| {
+            quote! { const _: usize = #view_ident::__ID_OFFSET; }
+        });
+        (
+            quote! {
+                #[doc(hidden)]
+                #[repr(C)]
+                #derive_id
+                #id_vis struct #id_ident #id_generics #id_where_clause {
+                    #(#id_fields,)*
+                }
+            },
+            quote! {
+                #id_struct_impls
+                impl #impl_generics #view_ident #ty_generics #where_clause {
+                    const __ID_OFFSET: usize = {
+                        let offset = core::mem::offset_of!(#ident #ty_generics, #first);
+                        assert!(
+                            offset % core::mem::align_of::<#id_ident #id_ty_generics>() == 0,
+                            "the id fields of the view are not laid out as its id, move them to the front",
+                        );
+                        offset
+                    };
+                }
+                #check_offset
+                impl #impl_generics Borrow<#id_ident #id_ty_generics> for #view_ident #ty_generics #where_clause {
+                    fn borrow(&self) -> &#id_ident #id_ty_generics {
+                        // the view is transparent, and the adjacent id fields of the
+                        // `repr(C)` struct have the layout of the `repr(C)` id when
+                        // the first one is at a multiple of the id alignment
+                        unsafe {
+                            &*(self as *const Self)
+                                .cast::<u8>()
+                                .add(Self::__ID_OFFSET)
+                                .cast()
+                        }
+                    }
+                }
+                #from_view
+            },
+        )
+    };
+    let pointer_borrows = pointer_borrows(
+        &input.generics,
+        quote!(#view_ident #ty_generics),
+        quote!(#id_ident #id_ty_generics),
+        id_fields.len() != 1,
+    );
+    let item_impls = item_impls(
+        &view_ident,
+        &input.generics,
+        quote!(#id_ident #id_ty_generics),
+        &readonly_ident,
+    );
+    let readonly_export = args.readonly.is_some().then(|| {
+        quote! { #vis use #mod_name::#readonly_ident; }
+    });
+    Ok(quote! {
+        #input
+        #[doc = #doc]
+        #[repr(transparent)]
+        #vis struct #view_ident #impl_generics (pub #ident #ty_generics) #where_clause;
+        #id_define
+        #readonly_export
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        #[expect(clippy::field_scoped_visibility_modifiers)]
+        mod #mod_name {
+            #[expect(clippy::wildcard_imports)]
+            use super::*;
+            use core::{
+                borrow::Borrow,
+                hash::{Hash, Hasher},
+                ops::Deref,
+            };
+            #readonly
+            #id_impls
+            #pointer_borrows
+            #item_impls
+            impl #impl_generics Deref for #view_ident #ty_generics #where_clause {
+                type Target = #ident #ty_generics;
+                #[inline]
+                fn deref(&self) -> &Self::Target {
+                    &self.0
+                }
+            }
+            impl #impl_generics From<#ident #ty_generics> for #view_ident #ty_generics #where_clause {
+                #[inline]
+                fn from(item: #ident #ty_generics) -> Self {
+                    Self(item)
+                }
+            }
+        }
+    })
+}
+
+/// Whether `attr` is `#[item]` or `#[item(..)]` without `view`, by any path
+fn is_plain_item(attr: &syn::Attribute) -> bool {
+    if attr
+        .path()
+        .segments
+        .last()
+        .is_none_or(|segment| segment.ident != "item")
+    {
+        return false;
+    }
+    match &attr.meta {
+        syn::Meta::Path(_) => true,
+        syn::Meta::List(_) => {
+            attr.parse_args::<ItemArgs>().is_ok_and(|args| !args.is_view())
+        }
+        syn::Meta::NameValue(_) => false,
+    }
+}
+
+fn is_repr_c(repr: &syn::Attribute) -> bool {
+    let mut c = false;
+    let _ = repr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("packed") || meta.path.is_ident("transparent") {
+            c = false;
+            return Err(meta.error("not C"));
+        }
+        c |= meta.path.is_ident("C");
+        if meta.input.peek(token::Paren) {
+            let _group: TokenTree = meta.input.parse()?;
+        }
+        Ok(())
+    });
+    c
+}
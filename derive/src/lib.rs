@@ -3,17 +3,30 @@ extern crate proc_macro;
 mod expand;
 
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{DeriveInput, parse_macro_input};
+use quote::{ToTokens, quote};
+use syn::DeriveInput;
 
 #[proc_macro_attribute]
-pub fn item(_: TokenStream, tokens: TokenStream) -> TokenStream {
+pub fn item(args: TokenStream, tokens: TokenStream) -> TokenStream {
     let original = tokens.clone();
-    let input = parse_macro_input!(tokens as DeriveInput);
 
-    expand::readonly(input)
+    syn::parse::<expand::ItemArgs>(args)
+        .and_then(|args| {
+            let input = syn::parse::<DeriveInput>(tokens)?;
+            if args.is_view() {
+                expand::view(args, input)
+            } else {
+                expand::readonly(args, input)
+            }
+        })
         .unwrap_or_else(|e| {
-            let original = proc_macro2::TokenStream::from(original);
+            let original = match syn::parse::<DeriveInput>(original.clone()) {
+                Ok(mut input) => {
+                    expand::strip_id_attrs(&mut input);
+                    input.into_token_stream()
+                }
+                Err(_) => proc_macro2::TokenStream::from(original),
+            };
             let compile_error = e.to_compile_error();
             quote! {
                 #original
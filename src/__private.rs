@@ -0,0 +1,63 @@
+//! Helpers used by the code generated from `#[mut_set::derive::item]`
+
+use core::hash::Hasher;
+
+pub use alloc::{boxed::Box, rc::Rc, sync::Arc};
+
+/// Panic when two id fields considered equal do not hash equally,
+/// called in debug builds for `#[id(hash_with = .., eq_with = ..)]`
+#[track_caller]
+pub fn check_eq_hash<T: ?Sized>(
+    field: &str,
+    a: &T,
+    b: &T,
+    hash: impl Fn(&T, &mut FnvHasher),
+) {
+    let mut hash_a = FnvHasher::default();
+    let mut hash_b = FnvHasher::default();
+    hash(a, &mut hash_a);
+    hash(b, &mut hash_b);
+    assert_eq!(
+        hash_a.finish(),
+        hash_b.finish(),
+        "`{field}`: values that are equal must hash equally, check `hash_with`/`eq_with`"
+    );
+}
+
+/// Deterministic 64-bit FNV-1a
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    #[inline]
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Implemented by `mut_set::intern::Symbol` only, checks the type of
+/// `#[id(intern)]` fields whatever name it is imported under
+#[cfg(feature = "std")]
+#[diagnostic::on_unimplemented(
+    message = "`intern` fields are declared as `mut_set::intern::Symbol`, found `{Self}`"
+)]
+pub trait InternSymbol {}
+
+#[cfg(feature = "std")]
+impl InternSymbol for crate::intern::Symbol {}
+
+#[cfg(feature = "std")]
+pub const fn check_intern_symbol<T: InternSymbol>() {}
@@ -0,0 +1,352 @@
+//! Item set with stable generational handles
+
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{BuildHasher, Hash, Hasher},
+    marker::PhantomData,
+};
+use hashbrown::HashTable;
+use std::hash::RandomState;
+
+use crate::{Item, MutSetExt};
+
+/// Link to an item of [`HandleSet`], an index plus the generation of its slot.
+///
+/// A handle stays valid across other insertions and removals. Once its item
+/// is removed, the slot generation moves on and the handle resolves to `None`,
+/// even if the slot is reused by another item.
+pub struct Handle<T> {
+    index: u32,
+    generation: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    /// Slot index, only unique among the live items
+    #[inline]
+    pub const fn index(&self) -> u32 {
+        self.index
+    }
+    #[inline]
+    pub const fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for Handle<T> {}
+impl<T> PartialEq for Handle<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+impl<T> Eq for Handle<T> {}
+impl<T> PartialOrd for Handle<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T> Ord for Handle<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.index, self.generation).cmp(&(other.index, other.generation))
+    }
+}
+impl<T> Hash for Handle<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({}v{})", self.index, self.generation)
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    item: Option<T>,
+}
+
+/// Slab of items indexed by id, where [`Handle`]s give O(1) access without hashing
+pub struct HandleSet<T: Item, S = RandomState> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    table: HashTable<u32>,
+    hash_builder: S,
+}
+
+impl<T: Item> HandleSet<T> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<T: Item> Default for HandleSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Item, S> HandleSet<T, S> {
+    pub const fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            table: HashTable::new(),
+            hash_builder,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    #[inline]
+    fn slot(&self, handle: Handle<T>) -> Option<&T> {
+        let slot = self.slots.get(handle.index as usize)?;
+        if slot.generation == handle.generation { slot.item.as_ref() } else { None }
+    }
+
+    #[inline]
+    fn handle(&self, index: u32) -> Handle<T> {
+        Handle {
+            index,
+            generation: self.slots[index as usize].generation,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.slot(handle).is_some()
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        self.slot(handle)
+    }
+
+    /// Mutable access to the non-id fields, in O(1) without hashing
+    pub fn get_mut_by_handle(
+        &mut self,
+        handle: Handle<T>,
+    ) -> Option<&mut T::IdReadonlyItem> {
+        self.slot(handle).map(|item| unsafe { item.__unsafe_deref_mut() })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let item = slot.item.as_ref()?;
+            Some((self.handle(index as u32), item))
+        })
+    }
+
+    pub fn clear(&mut self) {
+        let Self { slots, free, table, .. } = self;
+        for index in table.drain() {
+            free_slot(slots, free, index);
+        }
+    }
+}
+
+fn free_slot<T>(slots: &mut [Slot<T>], free: &mut Vec<u32>, index: u32) -> T {
+    let slot = &mut slots[index as usize];
+    let item = slot.item.take().unwrap();
+    // a slot whose generation is exhausted is never reused,
+    // so a stale handle can not alias a later item
+    if let Some(generation) = slot.generation.checked_add(1) {
+        slot.generation = generation;
+        free.push(index);
+    }
+    item
+}
+
+impl<T: Item, S: BuildHasher> HandleSet<T, S> {
+    /// Add `value` and return its handle.
+    ///
+    /// An existing item with the same id is replaced in place and keeps its handle.
+    pub fn insert(&mut self, value: T) -> Handle<T> {
+        self.replace(value).0
+    }
+
+    /// Add `value`, returning its handle and the replaced item with the same id
+    pub fn replace(&mut self, value: T) -> (Handle<T>, Option<T>) {
+        let hash = self.hash_builder.hash_one(&value);
+        let slots = &self.slots;
+        if let Some(&index) = self.table.find(hash, |&i| eq::<T, T>(slots, i, &value)) {
+            let old = self.slots[index as usize].item.replace(value);
+            return (self.handle(index), old);
+        }
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index as usize].item = Some(value);
+                index
+            }
+            None => {
+                let index = u32::try_from(self.slots.len()).expect("too many slots");
+                self.slots.push(Slot { generation: 0, item: Some(value) });
+                index
+            }
+        };
+        let Self { slots, hash_builder, .. } = self;
+        self.table.insert_unique(hash, index, |&i| {
+            hash_builder.hash_one(slots[i as usize].item.as_ref().unwrap())
+        });
+        (self.handle(index), None)
+    }
+
+    /// Handle of the item equal to `value`
+    pub fn handle_of<Q>(&self, value: &Q) -> Option<Handle<T>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = self.hash_builder.hash_one(value);
+        let index = *self.table.find(hash, |&i| eq(&self.slots, i, value))?;
+        Some(self.handle(index))
+    }
+
+    /// Remove the item of `handle`, every copy of `handle` becomes stale
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        let hash = self.hash_builder.hash_one(self.slot(handle)?);
+        if let Ok(entry) = self.table.find_entry(hash, |&i| i == handle.index) {
+            entry.remove();
+        }
+        Some(free_slot(&mut self.slots, &mut self.free, handle.index))
+    }
+
+    /// Remove and return the item equal to `value`
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = self.hash_builder.hash_one(value);
+        let slots = &self.slots;
+        let (index, _) =
+            self.table.find_entry(hash, |&i| eq(slots, i, value)).ok()?.remove();
+        Some(free_slot(&mut self.slots, &mut self.free, index))
+    }
+}
+
+#[inline]
+fn eq<T: Borrow<Q>, Q: ?Sized + Eq>(slots: &[Slot<T>], index: u32, value: &Q) -> bool {
+    slots[index as usize]
+        .item
+        .as_ref()
+        .is_some_and(|item| item.borrow() == value)
+}
+
+impl<T: Item, S: BuildHasher> MutSetExt<T> for HandleSet<T, S> {
+    type IterMut<'a>
+        = IterMut<'a, T>
+    where
+        Self: 'a;
+
+    fn get_mut<Q>(&mut self, value: &Q) -> Option<&mut T::IdReadonlyItem>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let handle = self.handle_of(value)?;
+        self.get_mut_by_handle(handle)
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        IterMut { inner: self.slots.iter() }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, S> crate::ParMutSetExt<T> for HandleSet<T, S>
+where
+    T: Item + Sync,
+    T::IdReadonlyItem: Send,
+    S: BuildHasher,
+{
+    type ParIterMut<'a>
+        = ParIterMut<'a, T>
+    where
+        Self: 'a;
+
+    fn par_iter_mut(&mut self) -> Self::ParIterMut<'_> {
+        ParIterMut {
+            inner: rayon::iter::IntoParallelIterator::into_par_iter(&*self.slots),
+        }
+    }
+}
+
+impl<T: Item + fmt::Debug, S> fmt::Debug for HandleSet<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Item, S: BuildHasher + Default> FromIterator<T> for HandleSet<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::with_hasher(S::default());
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Item, S: BuildHasher> Extend<T> for HandleSet<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+pub struct IterMut<'a, T: Item> {
+    inner: core::slice::Iter<'a, Slot<T>>,
+}
+
+impl<'a, T: Item> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T::IdReadonlyItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.by_ref().find_map(|slot| {
+            slot.item.as_ref().map(|item| unsafe { item.__unsafe_deref_mut() })
+        })
+    }
+}
+
+#[cfg(feature = "rayon")]
+pub struct ParIterMut<'a, T: Item> {
+    inner: rayon::slice::Iter<'a, Slot<T>>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T> rayon::iter::ParallelIterator for ParIterMut<'a, T>
+where
+    T: Item + Sync,
+    T::IdReadonlyItem: Send,
+{
+    type Item = &'a mut T::IdReadonlyItem;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>,
+    {
+        self.inner
+            .filter_map(|slot| {
+                slot.item.as_ref().map(|item| unsafe { item.__unsafe_deref_mut() })
+            })
+            .drive_unindexed(consumer)
+    }
+}
@@ -0,0 +1,241 @@
+//! Undo/redo history over [`Transactional`] collections
+
+use core::{borrow::Borrow, hash::Hash, mem, ops::Deref};
+use std::collections::{HashSet, VecDeque};
+
+use crate::{Item, Transactional};
+
+/// Wrapper recording the changes made through it, so that they can be undone and redone.
+///
+/// Changes are grouped into steps, a step ends at [`checkpoint`](Self::checkpoint),
+/// [`undo`](Self::undo) or [`redo`](Self::redo). Undo restores the order of an
+/// `IndexSet` too. Payloads borrowed by `get_mut`/`iter_mut` are cloned once per
+/// step, the state after the change is captured when the step is undone.
+/// ``` rust
+/// use mut_set::history::History;
+///
+/// #[derive(Clone)]
+/// #[mut_set::derive::item]
+/// pub struct Cell {
+///     #[id]
+///     pub name: String,
+///     pub area: f64,
+/// }
+/// # fn main() {
+/// let mut cells = History::new(indexmap::IndexSet::new());
+/// cells.insert(Cell { name: "a".into(), area: 1.0 });
+/// cells.checkpoint();
+/// cells.get_mut(&"a".to_string()).unwrap().area = 2.0;
+/// cells.undo();
+/// assert_eq!(cells[0].area, 1.0);
+/// cells.redo();
+/// assert_eq!(cells[0].area, 2.0);
+/// # }
+/// ```
+pub struct History<S, T>
+where
+    T: Item + Clone,
+    T::Id: Clone + Hash + Eq,
+    S: Transactional<T>,
+{
+    inner: S,
+    /// Changes of the open step
+    current: Vec<Undo<T, S::Position>>,
+    /// Ids saved in the open step, the first copy is enough
+    /// as the later changes are undone before it
+    saved: HashSet<T::Id>,
+    undo: VecDeque<Vec<Undo<T, S::Position>>>,
+    redo: Vec<Vec<Undo<T, S::Position>>>,
+    limit: usize,
+}
+
+/// Each change is stored as the operation undoing it, applying it gives the opposite one
+enum Undo<T: Item, P> {
+    Remove(T::Id),
+    Restore(P, T),
+    Swap(T),
+}
+
+impl<S, T> History<S, T>
+where
+    T: Item + Clone,
+    T::Id: Clone + Hash + Eq,
+    S: Transactional<T>,
+{
+    /// Unbounded history
+    pub fn new(inner: S) -> Self {
+        Self::with_limit(inner, usize::MAX)
+    }
+
+    /// Keep at most `limit` steps, dropping the oldest ones
+    pub fn with_limit(inner: S, limit: usize) -> Self {
+        Self {
+            inner,
+            current: Vec::new(),
+            saved: HashSet::new(),
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Close the open step, later changes are undone separately
+    pub fn checkpoint(&mut self) {
+        self.saved.clear();
+        if self.current.is_empty() {
+            return;
+        }
+        if self.undo.len() >= self.limit {
+            self.undo.pop_front();
+        }
+        if self.limit != 0 {
+            self.undo.push_back(mem::take(&mut self.current));
+        } else {
+            self.current.clear();
+        }
+    }
+
+    /// Number of steps that can be undone, including the open one
+    pub fn undo_steps(&self) -> usize {
+        self.undo.len() + usize::from(!self.current.is_empty())
+    }
+
+    pub fn redo_steps(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Forget every step, keeping the set as it is
+    pub fn clear_history(&mut self) {
+        self.current.clear();
+        self.saved.clear();
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Revert the last step, returns `false` if there is none
+    pub fn undo(&mut self) -> bool {
+        self.checkpoint();
+        let Some(step) = self.undo.pop_back() else {
+            return false;
+        };
+        let step = self.apply(step);
+        self.redo.push(step);
+        true
+    }
+
+    /// Apply again the last undone step, returns `false` if there is none
+    pub fn redo(&mut self) -> bool {
+        self.checkpoint();
+        let Some(step) = self.redo.pop() else {
+            return false;
+        };
+        let step = self.apply(step);
+        self.undo.push_back(step);
+        true
+    }
+
+    /// Apply `step` from its last change to its first one, returning the opposite step
+    fn apply(&mut self, step: Vec<Undo<T, S::Position>>) -> Vec<Undo<T, S::Position>> {
+        step.into_iter()
+            .rev()
+            .map(|undo| match undo {
+                Undo::Remove(id) => {
+                    let (position, item) = self.inner.__remove(&id).unwrap();
+                    Undo::Restore(position, item)
+                }
+                Undo::Restore(position, item) => {
+                    let id = item.to_id();
+                    self.inner.__undo_remove(position, item);
+                    Undo::Remove(id)
+                }
+                Undo::Swap(item) => Undo::Swap(self.inner.__replace(item).unwrap()),
+            })
+            .collect()
+    }
+
+    /// Record a change, which discards the undone steps
+    #[inline]
+    fn record(&mut self, undo: Undo<T, S::Position>) {
+        self.redo.clear();
+        self.current.push(undo);
+    }
+
+    #[inline]
+    fn save(
+        current: &mut Vec<Undo<T, S::Position>>,
+        saved: &mut HashSet<T::Id>,
+        item: &T,
+    ) {
+        if !saved.contains(item.id()) {
+            saved.insert(item.to_id());
+            current.push(Undo::Swap(item.clone()));
+        }
+    }
+
+    /// Add `value`, returns `false` and drops `value` when an equal item exists
+    pub fn insert(&mut self, value: T) -> bool {
+        let id = value.to_id();
+        let inserted = self.inner.__insert(value);
+        if inserted {
+            self.record(Undo::Remove(id));
+        }
+        inserted
+    }
+
+    /// Add `value`, replacing and returning the existing equal item
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        let id = value.to_id();
+        let old = self.inner.__replace(value);
+        self.record(match &old {
+            Some(old) => Undo::Swap(old.clone()),
+            None => Undo::Remove(id),
+        });
+        old
+    }
+
+    /// Remove and return the item equal to `value`
+    pub fn remove<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let (position, item) = self.inner.__remove(value)?;
+        self.record(Undo::Restore(position, item.clone()));
+        Some(item)
+    }
+
+    /// Same as [`MutSetExt::get_mut`](crate::MutSetExt::get_mut), saving the item first
+    pub fn get_mut<Q>(&mut self, value: &Q) -> Option<&mut T::IdReadonlyItem>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let item = self.inner.get_mut(value)?;
+        self.redo.clear();
+        Self::save(&mut self.current, &mut self.saved, item);
+        Some(item)
+    }
+
+    /// Same as [`MutSetExt::iter_mut`](crate::MutSetExt::iter_mut), saving every yielded item first
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T::IdReadonlyItem> {
+        self.redo.clear();
+        let Self { inner, current, saved, .. } = self;
+        inner.iter_mut().inspect(|item| Self::save(current, saved, item))
+    }
+}
+
+impl<S, T> Deref for History<S, T>
+where
+    T: Item + Clone,
+    T::Id: Clone + Hash + Eq,
+    S: Transactional<T>,
+{
+    type Target = S;
+    fn deref(&self) -> &S {
+        &self.inner
+    }
+}
@@ -0,0 +1,200 @@
+//! Ready-made functions for `#[id(into_hash_ord_fn = ...)]`
+//!
+//! ``` rust
+//! use mut_set::id_adapters;
+//!
+//! #[mut_set::derive::item]
+//! pub struct Cell {
+//!     #[id(into_hash_ord_fn = id_adapters::ascii_case_insensitive)]
+//!     pub name: String,
+//!     #[id(into_hash_ord_fn = id_adapters::f64_total)]
+//!     pub width: f64,
+//!     pub area: f64,
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! Every adapter returns a key whose `Hash`, `Eq` and `Ord` agree with each other.
+
+use core::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+use std::path::{Component, Path, PathBuf};
+
+macro_rules! total_float {
+    ($name:ident, $float:ty, $fn:ident) => {
+        /// Float key ordered by `total_cmp`, so `-0.0 < 0.0` and every `NaN` has its place
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name(pub $float);
+
+        impl PartialEq for $name {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                self.0.to_bits() == other.0.to_bits()
+            }
+        }
+        impl Eq for $name {}
+        impl PartialOrd for $name {
+            #[inline]
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for $name {
+            #[inline]
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+        impl Hash for $name {
+            #[inline]
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.to_bits().hash(state)
+            }
+        }
+
+        /// Compare floats by their total order, see
+        #[doc = concat!("[`", stringify!($name), "`]")]
+        #[inline]
+        pub const fn $fn(val: &$float) -> $name {
+            $name(*val)
+        }
+    };
+}
+
+total_float!(TotalF32, f32, f32_total);
+total_float!(TotalF64, f64, f64_total);
+
+/// Round `val` to a multiple of `1 / STEPS`, e.g. `f64_quantized::<1000>`
+/// treats values within the same `0.001` step as the same id.
+///
+/// `NaN` maps to `0` and out of range values saturate.
+#[inline]
+pub fn f64_quantized<const STEPS: u32>(val: &f64) -> i64 {
+    (val * f64::from(STEPS)).round() as i64
+}
+
+/// Round `val` to a multiple of `1 / STEPS`, see [`f64_quantized`]
+#[inline]
+pub fn f32_quantized<const STEPS: u32>(val: &f32) -> i64 {
+    f64_quantized::<STEPS>(&f64::from(*val))
+}
+
+/// String key ignoring ASCII case, without allocation
+#[derive(Debug, Clone, Copy)]
+pub struct AsciiCaseInsensitive<'a>(pub &'a str);
+
+impl AsciiCaseInsensitive<'_> {
+    #[inline]
+    fn folded(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.bytes().map(|b| b.to_ascii_lowercase())
+    }
+}
+
+impl PartialEq for AsciiCaseInsensitive<'_> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(other.0)
+    }
+}
+impl Eq for AsciiCaseInsensitive<'_> {}
+impl PartialOrd for AsciiCaseInsensitive<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for AsciiCaseInsensitive<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.folded().cmp(other.folded())
+    }
+}
+impl Hash for AsciiCaseInsensitive<'_> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.folded().for_each(|b| state.write_u8(b));
+        state.write_u8(0xff);
+    }
+}
+
+/// Compare strings ignoring ASCII case
+#[inline]
+pub fn ascii_case_insensitive(val: &str) -> AsciiCaseInsensitive<'_> {
+    AsciiCaseInsensitive(val)
+}
+
+/// String key compared by its Unicode lowercase form, without allocation
+#[derive(Debug, Clone, Copy)]
+pub struct UnicodeCaseInsensitive<'a>(pub &'a str);
+
+impl UnicodeCaseInsensitive<'_> {
+    #[inline]
+    fn folded(&self) -> impl Iterator<Item = char> + '_ {
+        self.0.chars().flat_map(char::to_lowercase)
+    }
+}
+
+impl PartialEq for UnicodeCaseInsensitive<'_> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.folded().eq(other.folded())
+    }
+}
+impl Eq for UnicodeCaseInsensitive<'_> {}
+impl PartialOrd for UnicodeCaseInsensitive<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for UnicodeCaseInsensitive<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.folded().cmp(other.folded())
+    }
+}
+impl Hash for UnicodeCaseInsensitive<'_> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.folded().for_each(|c| state.write_u32(c as u32));
+        state.write_u8(0xff);
+    }
+}
+
+/// Compare strings by their Unicode lowercase form
+#[inline]
+pub fn unicode_case_insensitive(val: &str) -> UnicodeCaseInsensitive<'_> {
+    UnicodeCaseInsensitive(val)
+}
+
+/// Compare strings without leading and trailing whitespace
+#[inline]
+pub fn trimmed(val: &str) -> &str {
+    val.trim()
+}
+
+/// Compare paths after lexically resolving `.` and `..`,
+/// e.g. `a/./b/../c` and `a/c` are the same id.
+///
+/// The file system is not touched, so symbolic links are not resolved.
+pub fn normalized_path(val: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in val.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                Some(Component::ParentDir | Component::CurDir) | None => {
+                    normalized.push(Component::ParentDir)
+                }
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
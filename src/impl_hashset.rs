@@ -2,11 +2,13 @@ use core::{
     borrow::Borrow,
     hash::{BuildHasher, Hash},
 };
-use indexmap::{IndexSet, set::Iter};
+use std::collections::{HashSet, hash_set::Iter};
 
-use crate::{Item, MutSetExt};
+#[cfg(feature = "rayon")]
+use crate::ParMutSetExt;
+use crate::{Item, MutSetExt, Transactional};
 
-impl<T: Item, S: BuildHasher> MutSetExt<T> for IndexSet<T, S> {
+impl<T: Item, S: BuildHasher> MutSetExt<T> for HashSet<T, S> {
     type IterMut<'a>
         = IterMut<'a, T>
     where
@@ -25,6 +27,64 @@ impl<T: Item, S: BuildHasher> MutSetExt<T> for IndexSet<T, S> {
     }
 }
 
+#[cfg(feature = "rayon")]
+impl<T, S> ParMutSetExt<T> for HashSet<T, S>
+where
+    T: Item + Sync,
+    T::IdReadonlyItem: Send,
+    S: BuildHasher,
+{
+    type ParIterMut<'a>
+        = ParIterMut<'a, T>
+    where
+        Self: 'a;
+
+    fn par_iter_mut(&mut self) -> Self::ParIterMut<'_> {
+        ParIterMut {
+            inner: rayon::iter::IntoParallelIterator::into_par_iter(&*self),
+        }
+    }
+}
+
+impl<T: Item, S: BuildHasher> Transactional<T> for HashSet<T, S> {
+    type Position = ();
+
+    fn __get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get(value)
+    }
+
+    fn __insert(&mut self, value: T) -> bool {
+        self.insert(value)
+    }
+
+    fn __replace(&mut self, value: T) -> Option<T> {
+        self.replace(value)
+    }
+
+    fn __remove<Q>(&mut self, value: &Q) -> Option<((), T)>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.take(value).map(|item| ((), item))
+    }
+
+    fn __undo_insert(&mut self, id: &T::Id)
+    where
+        T::Id: Hash + Eq,
+    {
+        self.remove(id);
+    }
+
+    fn __undo_remove(&mut self, _: (), value: T) {
+        self.insert(value);
+    }
+}
+
 pub struct IterMut<'a, T: Item> {
     inner: Iter<'a, T>,
 }
@@ -36,3 +96,26 @@ impl<'a, T: Item> Iterator for IterMut<'a, T> {
         self.inner.next().map(|item| unsafe { item.__unsafe_deref_mut() })
     }
 }
+
+#[cfg(feature = "rayon")]
+pub struct ParIterMut<'a, T: Item> {
+    inner: rayon::collections::hash_set::Iter<'a, T>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T> rayon::iter::ParallelIterator for ParIterMut<'a, T>
+where
+    T: Item + Sync,
+    T::IdReadonlyItem: Send,
+{
+    type Item = &'a mut T::IdReadonlyItem;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>,
+    {
+        self.inner
+            .map(|item| unsafe { item.__unsafe_deref_mut() })
+            .drive_unindexed(consumer)
+    }
+}
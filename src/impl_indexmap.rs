@@ -2,11 +2,18 @@ use core::{
     borrow::Borrow,
     hash::{BuildHasher, Hash},
 };
-use std::collections::{HashSet, hash_set::Iter};
+use indexmap::{IndexSet, set::Iter};
 
+#[cfg(feature = "std")]
+use crate::Transactional;
 use crate::{Item, MutSetExt};
+#[cfg(feature = "rayon")]
+use {
+    crate::ParMutSetExt,
+    rayon::iter::{IndexedParallelIterator, ParallelIterator},
+};
 
-impl<T: Item, S: BuildHasher> MutSetExt<T> for HashSet<T, S> {
+impl<T: Item, S: BuildHasher> MutSetExt<T> for IndexSet<T, S> {
     type IterMut<'a>
         = IterMut<'a, T>
     where
@@ -25,6 +32,67 @@ impl<T: Item, S: BuildHasher> MutSetExt<T> for HashSet<T, S> {
     }
 }
 
+#[cfg(feature = "rayon")]
+impl<T, S> ParMutSetExt<T> for IndexSet<T, S>
+where
+    T: Item + Sync,
+    T::IdReadonlyItem: Send,
+    S: BuildHasher,
+{
+    type ParIterMut<'a>
+        = ParIterMut<'a, T>
+    where
+        Self: 'a;
+
+    fn par_iter_mut(&mut self) -> Self::ParIterMut<'_> {
+        ParIterMut {
+            inner: rayon::iter::IntoParallelIterator::into_par_iter(&*self),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Item, S: BuildHasher> Transactional<T> for IndexSet<T, S> {
+    type Position = usize;
+
+    fn __get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get(value)
+    }
+
+    fn __insert(&mut self, value: T) -> bool {
+        self.insert(value)
+    }
+
+    fn __replace(&mut self, value: T) -> Option<T> {
+        self.replace(value)
+    }
+
+    fn __remove<Q>(&mut self, value: &Q) -> Option<(usize, T)>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.swap_remove_full(value)
+    }
+
+    fn __undo_insert(&mut self, id: &T::Id)
+    where
+        T::Id: Hash + Eq,
+    {
+        // the item is the last one, nothing is swapped
+        self.swap_remove(id);
+    }
+
+    fn __undo_remove(&mut self, index: usize, value: T) {
+        let (last, _) = self.insert_full(value);
+        self.swap_indices(index, last);
+    }
+}
+
 pub struct IterMut<'a, T: Item> {
     inner: Iter<'a, T>,
 }
@@ -36,3 +104,102 @@ impl<'a, T: Item> Iterator for IterMut<'a, T> {
         self.inner.next().map(|item| unsafe { item.__unsafe_deref_mut() })
     }
 }
+
+#[cfg(feature = "rayon")]
+pub struct ParIterMut<'a, T: Item> {
+    inner: indexmap::set::rayon::ParIter<'a, T>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T> rayon::iter::ParallelIterator for ParIterMut<'a, T>
+where
+    T: Item + Sync,
+    T::IdReadonlyItem: Send,
+{
+    type Item = &'a mut T::IdReadonlyItem;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>,
+    {
+        self.inner
+            .map(|item| unsafe { item.__unsafe_deref_mut() })
+            .drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.inner.len())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> rayon::iter::IndexedParallelIterator for ParIterMut<'_, T>
+where
+    T: Item + Sync,
+    T::IdReadonlyItem: Send,
+{
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::Consumer<Self::Item>,
+    {
+        self.inner
+            .map(|item| unsafe { item.__unsafe_deref_mut() })
+            .drive(consumer)
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: rayon::iter::plumbing::ProducerCallback<Self::Item>,
+    {
+        self.inner
+            .map(|item| unsafe { item.__unsafe_deref_mut() })
+            .with_producer(callback)
+    }
+}
+
+/// Parallel helpers over the contiguous entries of `IndexSet`
+#[cfg(feature = "rayon")]
+pub trait ParIndexSetExt<T: Item> {
+    /// Call `f` on every item in parallel
+    fn par_for_each_mut<F>(&mut self, f: F)
+    where
+        F: Fn(&mut T::IdReadonlyItem) + Sync + Send;
+    /// Keep the items for which `keep` returns `true`, `keep` runs in parallel
+    /// and the order of the kept items is preserved
+    fn par_retain_mut<F>(&mut self, keep: F)
+    where
+        F: Fn(&mut T::IdReadonlyItem) -> bool + Sync + Send;
+}
+
+#[cfg(feature = "rayon")]
+impl<T, S> ParIndexSetExt<T> for IndexSet<T, S>
+where
+    T: Item + Sync,
+    T::IdReadonlyItem: Send,
+    S: BuildHasher,
+{
+    fn par_for_each_mut<F>(&mut self, f: F)
+    where
+        F: Fn(&mut T::IdReadonlyItem) + Sync + Send,
+    {
+        self.par_iter_mut().for_each(f);
+    }
+
+    fn par_retain_mut<F>(&mut self, keep: F)
+    where
+        F: Fn(&mut T::IdReadonlyItem) -> bool + Sync + Send,
+    {
+        let keep: Vec<bool> = self.par_iter_mut().map(keep).collect();
+        debug_assert_eq!(keep.len(), self.len());
+        // `retain` visits the items in order
+        let mut index = 0;
+        self.retain(|_| {
+            index += 1;
+            keep[index - 1]
+        });
+    }
+}
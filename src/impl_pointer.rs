@@ -0,0 +1,167 @@
+//! `IndexSet` of `Box`, `Rc` and `Arc` items, viewed through the pointers one by
+//! one. `Rc`/`Arc` items are cloned before being borrowed mutably when shared.
+
+use alloc::{boxed::Box, rc::Rc, sync::Arc};
+use core::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash, Hasher},
+    ops::Deref,
+};
+use indexmap::{Equivalent, IndexSet, set::Iter, set::MutableValues};
+
+use crate::{Item, MutSetExt};
+
+/// Key looked up in a set of pointers, compared with the pointed item
+struct Pointee<'a, Q: ?Sized>(&'a Q);
+
+impl<Q: ?Sized + Hash> Hash for Pointee<'_, Q> {
+    /// Same as the pointer, which hashes the item, which hashes as its borrowed key
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<Q, P> Equivalent<P> for Pointee<'_, Q>
+where
+    Q: ?Sized + Eq,
+    P: Deref<Target: Borrow<Q>>,
+{
+    #[inline]
+    fn equivalent(&self, key: &P) -> bool {
+        self.0 == (**key).borrow()
+    }
+}
+
+macro_rules! impl_pointer {
+    ($($ptr:ident [$($bound:tt)*] shared: $shared:literal, make_mut: $make_mut:expr;)*) => {
+        $(
+            impl<T: Item $($bound)*, S: BuildHasher> MutSetExt<T> for IndexSet<$ptr<T>, S> {
+                type IterMut<'a>
+                    = IterMut<'a, $ptr<T>>
+                where
+                    Self: 'a;
+
+                fn get_mut<Q>(&mut self, value: &Q) -> Option<&mut T::IdReadonlyItem>
+                where
+                    T: Borrow<Q>,
+                    Q: ?Sized + Hash + Eq,
+                {
+                    let (_, item) = self.get_full_mut2(&Pointee(value))?;
+                    // a clone is equal, so the item stays in place
+                    Some(($make_mut)(item).id_readonly())
+                }
+
+                fn iter_mut(&mut self) -> Self::IterMut<'_> {
+                    if $shared {
+                        self.retain2(|item| {
+                            ($make_mut)(item);
+                            true
+                        });
+                    }
+                    IterMut { inner: self.iter() }
+                }
+            }
+        )*
+    };
+}
+
+impl_pointer! {
+    Box [] shared: false, make_mut: <Box<T> as AsMut<T>>::as_mut;
+    Rc [+ Clone] shared: true, make_mut: Rc::make_mut;
+    Arc [+ Clone] shared: true, make_mut: Arc::make_mut;
+}
+
+/// The `Sync` pointers are split between threads as they are
+#[cfg(feature = "rayon")]
+macro_rules! impl_par_pointer {
+    ($($ptr:ident [$($bound:tt)*];)*) => {
+        $(
+            impl<T, S> crate::ParMutSetExt<T> for IndexSet<$ptr<T>, S>
+            where
+                T: Item + Sync $($bound)*,
+                T::IdReadonlyItem: Send,
+                S: BuildHasher,
+            {
+                type ParIterMut<'a>
+                    = ParIterMut<'a, $ptr<T>>
+                where
+                    Self: 'a,
+                    T: 'a;
+
+                fn par_iter_mut(&mut self) -> Self::ParIterMut<'_> {
+                    // unshares the items
+                    _ = MutSetExt::iter_mut(self);
+                    ParIterMut {
+                        inner: rayon::iter::IntoParallelIterator::into_par_iter(&*self),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "rayon")]
+impl_par_pointer! {
+    Box [];
+    Arc [+ Send + Clone];
+}
+
+#[cfg(feature = "rayon")]
+impl<T, S> crate::ParMutSetExt<T> for IndexSet<Rc<T>, S>
+where
+    T: Item + Sync + Clone,
+    T::IdReadonlyItem: Send,
+    S: BuildHasher,
+{
+    type ParIterMut<'a>
+        = rayon::vec::IntoIter<&'a mut T::IdReadonlyItem>
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn par_iter_mut(&mut self) -> Self::ParIterMut<'_> {
+        // `Rc` is not `Sync`, so the set can not be split between threads, the
+        // items are collected first
+        rayon::iter::IntoParallelIterator::into_par_iter(
+            self.iter_mut().collect::<alloc::vec::Vec<_>>(),
+        )
+    }
+}
+
+pub struct IterMut<'a, P> {
+    inner: Iter<'a, P>,
+}
+
+impl<'a, P: Deref<Target: Item>> Iterator for IterMut<'a, P> {
+    type Item = &'a mut <P::Target as Item>::IdReadonlyItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // every item is owned by its pointer alone, and the set is borrowed mutably
+        self.inner.next().map(|item| unsafe { (**item).__unsafe_deref_mut() })
+    }
+}
+
+#[cfg(feature = "rayon")]
+pub struct ParIterMut<'a, P: Sync> {
+    inner: indexmap::set::rayon::ParIter<'a, P>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, P> rayon::iter::ParallelIterator for ParIterMut<'a, P>
+where
+    P: Deref<Target: Item> + Sync,
+    <P::Target as Item>::IdReadonlyItem: Send,
+{
+    type Item = &'a mut <P::Target as Item>::IdReadonlyItem;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>,
+    {
+        // every item is owned by its pointer alone, and the set is borrowed mutably
+        self.inner
+            .map(|item| unsafe { (**item).__unsafe_deref_mut() })
+            .drive_unindexed(consumer)
+    }
+}
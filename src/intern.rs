@@ -0,0 +1,258 @@
+//! Global string interner for ids, see `#[id(intern)]`
+//!
+//! Every interned string is leaked into a process-wide table behind a
+//! `LazyLock<RwLock<_>>`, and is never freed: the memory grows with the number
+//! of distinct strings ever interned, so intern names from a bounded set such
+//! as a library, not from unbounded input. Interning a new string takes the
+//! write lock, looking up an existing one the read lock.
+
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, RwLock},
+};
+
+use crate::PrehashedId;
+
+struct Interner {
+    indexes: HashMap<&'static str, u32>,
+    names: Vec<&'static str>,
+}
+
+/// Strings are leaked, they live as long as the process
+static INTERNER: LazyLock<RwLock<Interner>> = LazyLock::new(|| {
+    RwLock::new(Interner { indexes: HashMap::new(), names: Vec::new() })
+});
+
+/// Hash of a [`Str`] that was never interned, no symbol has this index
+const UNKNOWN: u32 = u32::MAX;
+
+/// Interned string, `Copy` and hashed as its `u32` index.
+///
+/// Symbols are equal when their strings are, and ordered by their strings.
+/// Hashing and equality compare the indexes, while reading the string through
+/// `as_str`, `Deref`, `Ord`, `Display` and the like takes a read lock of the
+/// global interner, so sort or print many symbols from their strings copied out.
+/// Look up sets by `&str` through [`Str`].
+/// ``` rust
+/// use mut_set::intern::{Str, Symbol};
+///
+/// #[derive(Debug)]
+/// #[mut_set::derive::item]
+/// pub struct Pin {
+///     #[id(intern)]
+///     pub name: Symbol,
+///     pub cap: f64,
+/// }
+/// # fn main() {
+/// let mut pins = indexmap::IndexSet::new();
+/// pins.insert(Pin { name: "a".into(), cap: 1.0 });
+/// let name: Symbol = pins[0].name;
+/// assert_eq!(name, "a");
+/// assert_eq!(pins.get(Str::new("a")).unwrap().cap, 1.0);
+/// assert!(pins.get(Str::new("b")).is_none());
+/// # }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Intern `name`, returning the existing symbol if any
+    pub fn new(name: &str) -> Self {
+        if let Some(symbol) = Self::get(name) {
+            return symbol;
+        }
+        let mut interner = INTERNER.write().unwrap();
+        if let Some(&index) = interner.indexes.get(name) {
+            return Self(index);
+        }
+        let index = u32::try_from(interner.names.len())
+            .ok()
+            .filter(|&index| index != UNKNOWN)
+            .expect("too many interned strings");
+        let name: &'static str = Box::leak(name.into());
+        interner.indexes.insert(name, index);
+        interner.names.push(name);
+        Self(index)
+    }
+
+    /// Symbol of `name` if it has been interned, without interning it
+    pub fn get(name: &str) -> Option<Self> {
+        INTERNER.read().unwrap().indexes.get(name).copied().map(Self)
+    }
+
+    /// String of the symbol, read under the lock of the interner
+    pub fn as_str(self) -> &'static str {
+        INTERNER.read().unwrap().names[self.0 as usize]
+    }
+
+    /// Index of the symbol, in interning order
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+impl PrehashedId for Symbol {}
+
+impl Deref for Symbol {
+    type Target = str;
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<Str> for Symbol {
+    #[inline]
+    fn borrow(&self) -> &Str {
+        Str::new(self.as_str())
+    }
+}
+
+impl PartialOrd for Symbol {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.0 == other.0 {
+            Ordering::Equal
+        } else {
+            // a single lock for both strings
+            let names = &INTERNER.read().unwrap().names;
+            names[self.0 as usize].cmp(names[other.0 as usize])
+        }
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl From<&str> for Symbol {
+    #[inline]
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for Symbol {
+    #[inline]
+    fn from(name: String) -> Self {
+        Self::new(&name)
+    }
+}
+
+impl Default for Symbol {
+    /// The empty string
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// String looking up [`Symbol`]s, hashed as the index of its symbol.
+///
+/// `Str::new("a")` finds the items whose interned id is `"a"` without interning it.
+#[repr(transparent)]
+pub struct Str(str);
+
+impl Str {
+    #[inline]
+    pub fn new(name: &str) -> &Self {
+        // `Str` is a transparent wrapper of `str`
+        unsafe { &*(name as *const str as *const Self) }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PrehashedId for Str {}
+
+impl Hash for Str {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Symbol::get(&self.0).map_or(UNKNOWN, Symbol::index).hash(state)
+    }
+}
+
+impl PartialEq for Str {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Str {}
+
+impl PartialOrd for Str {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Str {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl fmt::Debug for Str {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
@@ -0,0 +1,341 @@
+//! Append-only journal keeping a set on disk
+//!
+//! A journal directory holds two files of JSON lines, each line prefixed by
+//! its FNV-1a checksum:
+//! + `snapshot.jsonl`, the items at the last compaction, replaced atomically
+//! + `journal.jsonl`, every change since then
+//!
+//! Both start with the generation of the compaction they belong to, so a
+//! crash in the middle of [`Journaled::compact`] is recovered as well.
+
+use core::{borrow::Borrow, hash::Hash, hash::Hasher, marker::PhantomData, ops::Deref};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{__private::FnvHasher, Item, Transactional};
+
+const SNAPSHOT: &str = "snapshot.jsonl";
+const JOURNAL: &str = "journal.jsonl";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Record<T> {
+    Generation(u64),
+    Put(T),
+    Remove(T),
+}
+
+fn checksum(json: &[u8]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(json);
+    hasher.finish()
+}
+
+fn encode<T: Serialize>(record: &Record<T>) -> io::Result<Vec<u8>> {
+    let json = serde_json::to_vec(record)?;
+    let mut line = format!("{:016x} ", checksum(&json)).into_bytes();
+    line.extend(json);
+    line.push(b'\n');
+    Ok(line)
+}
+
+fn decode<T: DeserializeOwned>(line: &[u8]) -> Option<Record<T>> {
+    let (sum, json) = line.split_at_checked(17)?;
+    let sum = u64::from_str_radix(core::str::from_utf8(&sum[..16]).ok()?, 16).ok()?;
+    if sum != checksum(json) {
+        return None;
+    }
+    serde_json::from_slice(json).ok()
+}
+
+fn invalid_data(file: &Path, line: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: line {line} is corrupted", file.display()),
+    )
+}
+
+/// Records of `file` and the length of its valid part, trailing records
+/// that are incomplete or corrupted are left out as a torn write
+fn read<T: DeserializeOwned>(file: &Path) -> io::Result<(Vec<Record<T>>, u64)> {
+    let bytes = match fs::read(file) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    let mut records = Vec::new();
+    let mut valid = 0;
+    let mut torn = None;
+    let mut offset = 0;
+    for (n, line) in bytes.split_inclusive(|&b| b == b'\n').enumerate() {
+        offset += line.len();
+        match line.strip_suffix(b"\n").and_then(decode) {
+            Some(record) => {
+                if let Some(line) = torn {
+                    return Err(invalid_data(file, line));
+                }
+                records.push(record);
+                valid = offset;
+            }
+            None => torn = torn.or(Some(n + 1)),
+        }
+    }
+    Ok((records, valid as u64))
+}
+
+/// Wrapper writing every change of the set to a journal directory.
+///
+/// Each change is appended with a single write before it is applied, so the
+/// set is left unchanged when writing fails. The write is not synced: it
+/// survives a crash of the process, call [`sync`](Self::sync) to survive a
+/// crash of the system too. A record torn by a crash is dropped when the
+/// directory is opened again, see [`torn_bytes`](Self::torn_bytes).
+///
+/// Removed items are written in full, so `T::Id` does not need to be serializable.
+/// ``` rust
+/// use mut_set::journal::Journaled;
+///
+/// #[derive(Clone, serde::Serialize, serde::Deserialize)]
+/// #[mut_set::derive::item]
+/// pub struct Cell {
+///     #[id]
+///     pub name: String,
+///     pub area: f64,
+/// }
+/// # fn main() -> std::io::Result<()> {
+/// # let dir = std::env::temp_dir().join(format!("mut_set_doc_{}", std::process::id()));
+/// let mut cells: Journaled<indexmap::IndexSet<Cell>, _> = Journaled::open(&dir)?;
+/// cells.insert(Cell { name: "a".into(), area: 1.0 })?;
+/// cells.modify(&"a".to_string(), |cell| cell.area = 2.0)?;
+/// drop(cells);
+/// let cells: Journaled<indexmap::IndexSet<Cell>, _> = Journaled::open(&dir)?;
+/// assert_eq!(cells[0].area, 2.0);
+/// # std::fs::remove_dir_all(&dir)
+/// # }
+/// ```
+pub struct Journaled<S, T> {
+    inner: S,
+    dir: PathBuf,
+    log: Log,
+    generation: u64,
+    torn_bytes: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+struct Log {
+    file: File,
+    len: u64,
+    records: usize,
+}
+
+impl Log {
+    fn create(path: &Path, generation: u64) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        let header = encode::<()>(&Record::Generation(generation))?;
+        file.write_all(&header)?;
+        file.sync_all()?;
+        Ok(Self {
+            file: OpenOptions::new().append(true).open(path)?,
+            len: header.len() as u64,
+            records: 0,
+        })
+    }
+
+    fn append<T: Serialize>(&mut self, record: &Record<T>) -> io::Result<()> {
+        let line = encode(record)?;
+        if let Err(e) = self.file.write_all(&line) {
+            // do not leave a partial record in front of the next ones
+            _ = self.file.set_len(self.len);
+            return Err(e);
+        }
+        self.len += line.len() as u64;
+        self.records += 1;
+        Ok(())
+    }
+}
+
+impl<S, T> Journaled<S, T>
+where
+    S: Transactional<T> + Default,
+    T: Item + Serialize + DeserializeOwned,
+{
+    /// Rebuild the set from the journal directory `dir`, creating it if needed
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let mut inner = S::default();
+
+        let snapshot = dir.join(SNAPSHOT);
+        let mut generation = 0;
+        if snapshot.exists() {
+            // the snapshot is renamed in place only once complete
+            let (records, len) = read::<T>(&snapshot)?;
+            if len != fs::metadata(&snapshot)?.len() {
+                return Err(invalid_data(&snapshot, records.len() + 1));
+            }
+            let mut records = records.into_iter();
+            match records.next() {
+                Some(Record::Generation(g)) => generation = g,
+                _ => return Err(invalid_data(&snapshot, 1)),
+            }
+            for (n, record) in records.enumerate() {
+                match record {
+                    Record::Put(item) => _ = inner.__replace(item),
+                    _ => return Err(invalid_data(&snapshot, n + 2)),
+                }
+            }
+        }
+
+        let journal = dir.join(JOURNAL);
+        let (records, len) = read::<T>(&journal)?;
+        let mut records = records.into_iter();
+        let (log, torn_bytes) = match records.next() {
+            Some(Record::Generation(g)) if g == generation => {
+                let mut count = 0;
+                for (n, record) in records.enumerate() {
+                    match record {
+                        Record::Put(item) => _ = inner.__replace(item),
+                        Record::Remove(item) => _ = inner.__remove::<T>(&item),
+                        Record::Generation(_) => {
+                            return Err(invalid_data(&journal, n + 2));
+                        }
+                    }
+                    count += 1;
+                }
+                let file = OpenOptions::new().append(true).open(&journal)?;
+                let torn_bytes = file.metadata()?.len() - len;
+                file.set_len(len)?;
+                (Log { file, len, records: count }, torn_bytes)
+            }
+            Some(Record::Generation(g)) if g > generation => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{}: the snapshot of generation {g} is missing",
+                        dir.display()
+                    ),
+                ));
+            }
+            // a new directory, or left from an interrupted compaction
+            None => (Log::create(&journal, generation)?, 0),
+            Some(Record::Generation(g)) if g < generation => {
+                (Log::create(&journal, generation)?, 0)
+            }
+            Some(_) => return Err(invalid_data(&journal, 1)),
+        };
+        Ok(Self {
+            inner,
+            dir,
+            log,
+            generation,
+            torn_bytes,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Add `value`, returns `false` and drops `value` when an equal item exists
+    pub fn insert(&mut self, value: T) -> io::Result<bool> {
+        if self.inner.__get::<T>(&value).is_some() {
+            return Ok(false);
+        }
+        self.log.append(&Record::Put(&value))?;
+        Ok(self.inner.__insert(value))
+    }
+
+    /// Add `value`, replacing and returning the existing equal item
+    pub fn replace(&mut self, value: T) -> io::Result<Option<T>> {
+        self.log.append(&Record::Put(&value))?;
+        Ok(self.inner.__replace(value))
+    }
+
+    /// Remove and return the item equal to `value`
+    pub fn remove<Q>(&mut self, value: &Q) -> io::Result<Option<T>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let Some(item) = self.inner.__get(value) else {
+            return Ok(None);
+        };
+        self.log.append(&Record::Remove(item))?;
+        Ok(self.inner.__remove(value).map(|(_, item)| item))
+    }
+
+    /// Apply `f` to the item equal to `value` and write the result,
+    /// the item is restored when writing fails
+    pub fn modify<Q, R>(
+        &mut self,
+        value: &Q,
+        f: impl FnOnce(&mut T::IdReadonlyItem) -> R,
+    ) -> io::Result<Option<R>>
+    where
+        T: Borrow<Q> + Clone,
+        Q: ?Sized + Hash + Eq,
+    {
+        let Some(item) = self.inner.get_mut(value) else {
+            return Ok(None);
+        };
+        let before = T::clone(item);
+        let out = f(item);
+        if let Err(e) = self.log.append(&Record::Put(&**item)) {
+            self.inner.__replace(before);
+            return Err(e);
+        }
+        Ok(Some(out))
+    }
+
+    /// Write the whole set as the new snapshot and empty the journal
+    pub fn compact(&mut self) -> io::Result<()>
+    where
+        for<'a> &'a S: IntoIterator<Item = &'a T>,
+    {
+        let generation = self.generation + 1;
+        let tmp = self.dir.join(format!("{SNAPSHOT}.tmp"));
+        let mut file = io::BufWriter::new(File::create(&tmp)?);
+        file.write_all(&encode::<()>(&Record::Generation(generation))?)?;
+        for item in &self.inner {
+            file.write_all(&encode(&Record::Put(item))?)?;
+        }
+        file.into_inner()
+            .map_err(io::IntoInnerError::into_error)?
+            .sync_all()?;
+        fs::rename(&tmp, self.dir.join(SNAPSHOT))?;
+        #[cfg(unix)]
+        File::open(&self.dir)?.sync_all()?;
+        self.log = Log::create(&self.dir.join(JOURNAL), generation)?;
+        self.generation = generation;
+        Ok(())
+    }
+}
+
+impl<S, T> Journaled<S, T> {
+    /// Flush the journal to the disk
+    pub fn sync(&self) -> io::Result<()> {
+        self.log.file.sync_data()
+    }
+
+    /// Number of changes written since the last compaction
+    pub fn journal_len(&self) -> usize {
+        self.log.records
+    }
+
+    /// Size of the torn record dropped when the journal was opened
+    pub fn torn_bytes(&self) -> u64 {
+        self.torn_bytes
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, T> Deref for Journaled<S, T> {
+    type Target = S;
+    fn deref(&self) -> &S {
+        &self.inner
+    }
+}
@@ -1,22 +1,57 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod derive {
     pub use mut_set_derive::item;
 }
 
+#[doc(hidden)]
+pub mod __private;
+#[cfg(feature = "std")]
+pub mod handle;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "id_adapters")]
+pub mod id_adapters;
+#[cfg(feature = "std")]
 mod impl_hashset;
 mod impl_indexmap;
+mod impl_pointer;
+#[cfg(feature = "std")]
+pub mod intern;
+#[cfg(feature = "journal")]
+pub mod journal;
+#[cfg(feature = "std")]
+pub mod observe;
+#[cfg(feature = "std")]
+pub mod persistent;
+#[cfg(feature = "std")]
+pub mod small;
+pub mod sorted_vec;
+#[cfg(feature = "std")]
+pub mod sync;
+#[cfg(feature = "std")]
+pub mod tracked;
+#[cfg(feature = "std")]
+pub mod transaction;
 use core::{
     borrow::Borrow,
     hash::{BuildHasher, Hash},
     ops::Deref,
 };
+#[cfg(feature = "rayon")]
+pub use impl_indexmap::ParIndexSetExt;
+#[cfg(feature = "std")]
+pub use transaction::Transactional;
 
 /// Extend  `HashSet`/`IndexSet` with `get_mut`/`iter_mut`
 pub trait MutSetExt<T: Item> {
-    type IterMut<'a>
+    type IterMut<'a>: Iterator<Item = &'a mut T::IdReadonlyItem>
     where
-        Self: 'a;
+        Self: 'a,
+        T: 'a;
     fn get_mut<Q>(&mut self, value: &Q) -> Option<&mut T::IdReadonlyItem>
     where
         T: Borrow<Q>,
@@ -24,6 +59,17 @@ pub trait MutSetExt<T: Item> {
     fn iter_mut(&mut self) -> Self::IterMut<'_>;
 }
 
+/// Extend [`MutSetExt`] with `par_iter_mut`, for items shared between threads
+#[cfg(feature = "rayon")]
+pub trait ParMutSetExt<T: Item>: MutSetExt<T> {
+    type ParIterMut<'a>: rayon::iter::ParallelIterator<Item = &'a mut T::IdReadonlyItem>
+    where
+        Self: 'a,
+        T: 'a;
+    /// Parallel version of `iter_mut`
+    fn par_iter_mut(&mut self) -> Self::ParIterMut<'_>;
+}
+
 pub trait Item
 where
     Self: Sized + Eq + Hash + Borrow<Self::Id>,
@@ -33,6 +79,13 @@ where
     fn id(&self) -> &Self::Id {
         self.borrow()
     }
+    /// Clone the id fields out of the item
+    fn to_id(&self) -> Self::Id
+    where
+        Self::Id: Clone,
+    {
+        self.id().clone()
+    }
     fn id_readonly(&mut self) -> &mut Self::IdReadonlyItem {
         unsafe { self.__unsafe_deref_mut() }
     }
@@ -43,27 +96,128 @@ where
     unsafe fn __unsafe_deref_mut(&self) -> &mut Self::IdReadonlyItem;
 }
 
+/// Items whose id is hashed by [`NoHashBuildHasher`] as itself,
+/// implemented by `#[id(prehashed)]`
+pub trait Prehashed: Item<Id: PrehashedId> {
+    /// Always [`NoHashBuildHasher`], named here so that the aliases
+    /// below only accept `Prehashed` items
+    type BuildHasher: BuildHasher + Default;
+}
+
+/// Ids whose `Hash` makes a single integer write, which is used as the hash
+/// by [`NoHashHasher`], e.g. integers that are already well distributed
+pub trait PrehashedId: Hash {}
+
+macro_rules! prehashed_id {
+    ($($t:ty),*) => {
+        $(impl PrehashedId for $t {})*
+    };
+}
+prehashed_id!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// `IndexSet` of [`Prehashed`] items, which are not hashed again
+pub type PrehashedIndexSet<T> = indexmap::IndexSet<T, <T as Prehashed>::BuildHasher>;
+/// `HashSet` of [`Prehashed`] items, which are not hashed again
+#[cfg(feature = "std")]
+pub type PrehashedHashSet<T> =
+    std::collections::HashSet<T, <T as Prehashed>::BuildHasher>;
+
+/// `BuildHasher` for `#[item(cache_hash)]` items, which write their cached hash
+pub type PrehashedBuildHasher = NoHashBuildHasher;
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct NoHashBuildHasher;
 impl BuildHasher for NoHashBuildHasher {
     type Hasher = NoHashHasher;
     #[inline]
     fn build_hasher(&self) -> Self::Hasher {
-        NoHashHasher(0)
+        NoHashHasher::default()
+    }
+}
+
+/// Hasher using the integer written to it as the hash.
+///
+/// A single integer is its own hash, see [`PrehashedId`]. Values writing several
+/// integers, such as `Option<u64>` or tuples, mix them in the same way in every
+/// build, so they still hash consistently but lose the benefit of skipping hashing.
+#[derive(Debug, Default)]
+pub struct NoHashHasher {
+    hash: u64,
+    written: bool,
+}
+
+impl NoHashHasher {
+    #[inline]
+    fn add(&mut self, i: u64) {
+        self.hash = if self.written {
+            (self.hash.rotate_left(5) ^ i).wrapping_mul(0x517c_c1b7_2722_0a95)
+        } else {
+            i
+        };
+        self.written = true;
     }
 }
-pub struct NoHashHasher(u64);
+
 impl core::hash::Hasher for NoHashHasher {
     #[inline]
     fn finish(&self) -> u64 {
-        self.0
+        self.hash
+    }
+    /// Bytes are read as little-endian integers of 8 bytes
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut buf = [0; 8];
+            buf[..chunk.len()].copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(buf));
+        }
+    }
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add(u64::from(i))
+    }
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add(u64::from(i))
+    }
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add(u64::from(i))
     }
     #[inline]
     fn write_u64(&mut self, i: u64) {
-        self.0 = i
+        self.add(i)
+    }
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.add(i as u64 ^ (i >> 64) as u64)
+    }
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64)
+    }
+    #[inline]
+    fn write_i8(&mut self, i: i8) {
+        self.write_u8(i as u8)
+    }
+    #[inline]
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16)
+    }
+    #[inline]
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32)
+    }
+    #[inline]
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64)
+    }
+    #[inline]
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128)
     }
     #[inline]
-    fn write(&mut self, _bytes: &[u8]) {
-        unimplemented!()
+    fn write_isize(&mut self, i: isize) {
+        self.write_usize(i as usize)
     }
 }
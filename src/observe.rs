@@ -0,0 +1,297 @@
+//! Change notifications over [`Transactional`] collections
+
+use core::{
+    borrow::Borrow,
+    fmt,
+    hash::Hash,
+    ops::{Deref, DerefMut},
+};
+use std::sync::mpsc;
+
+use crate::{Item, Transactional};
+
+/// Change made through [`Observed`]
+pub enum Event<'a, T: Item> {
+    Inserted(&'a T),
+    Removed(T),
+    Replaced {
+        old: T,
+        new: &'a T,
+    },
+    /// The non-id fields of the item may have been written
+    Modified(&'a T::Id),
+}
+
+impl<T: Item + Clone> Event<'_, T>
+where
+    T::Id: Clone,
+{
+    pub fn to_owned(&self) -> OwnedEvent<T> {
+        match self {
+            Self::Inserted(item) => OwnedEvent::Inserted((*item).clone()),
+            Self::Removed(item) => OwnedEvent::Removed(item.clone()),
+            Self::Replaced { old, new } => {
+                OwnedEvent::Replaced { old: old.clone(), new: (*new).clone() }
+            }
+            Self::Modified(id) => OwnedEvent::Modified((*id).clone()),
+        }
+    }
+}
+
+impl<T: Item + fmt::Debug> fmt::Debug for Event<'_, T>
+where
+    T::Id: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inserted(item) => f.debug_tuple("Inserted").field(item).finish(),
+            Self::Removed(item) => f.debug_tuple("Removed").field(item).finish(),
+            Self::Replaced { old, new } => f
+                .debug_struct("Replaced")
+                .field("old", old)
+                .field("new", new)
+                .finish(),
+            Self::Modified(id) => f.debug_tuple("Modified").field(id).finish(),
+        }
+    }
+}
+
+/// [`Event`] sent through [`Observed::subscribe`] channels
+pub enum OwnedEvent<T: Item> {
+    Inserted(T),
+    Removed(T),
+    Replaced { old: T, new: T },
+    Modified(T::Id),
+}
+
+impl<T: Item + fmt::Debug> fmt::Debug for OwnedEvent<T>
+where
+    T::Id: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inserted(item) => f.debug_tuple("Inserted").field(item).finish(),
+            Self::Removed(item) => f.debug_tuple("Removed").field(item).finish(),
+            Self::Replaced { old, new } => f
+                .debug_struct("Replaced")
+                .field("old", old)
+                .field("new", new)
+                .finish(),
+            Self::Modified(id) => f.debug_tuple("Modified").field(id).finish(),
+        }
+    }
+}
+
+/// Key of an observer, to remove it by [`Observed::unobserve`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(u64);
+
+/// Returns `false` to be unregistered
+type Callback<T> = Box<dyn FnMut(&Event<'_, T>) -> bool>;
+
+struct Observers<T: Item> {
+    next_id: u64,
+    list: Vec<(ObserverId, Callback<T>)>,
+}
+
+impl<T: Item> Observers<T> {
+    fn notify(&mut self, event: &Event<'_, T>) {
+        self.list.retain_mut(|(_, callback)| callback(event));
+    }
+}
+
+/// Wrapper notifying observers of every change made through it.
+///
+/// Observers run synchronously, in registration order, right after the change.
+/// Mutable borrows are reported as [`Event::Modified`] once they end.
+/// ``` rust
+/// use mut_set::observe::{Event, Observed};
+///
+/// #[derive(Clone)]
+/// #[mut_set::derive::item]
+/// pub struct Cell {
+///     #[id]
+///     pub name: String,
+///     pub area: f64,
+/// }
+/// # fn main() {
+/// let mut cells = Observed::new(indexmap::IndexSet::new());
+/// cells.observe(|event: &Event<'_, Cell>| {
+///     if let Event::Modified(name) = event {
+///         println!("{name} changed");
+///     }
+/// });
+/// let events = cells.subscribe();
+/// cells.insert(Cell { name: "a".into(), area: 1.0 });
+/// cells.get_mut(&"a".to_string()).unwrap().area = 2.0;
+/// assert_eq!(events.try_iter().count(), 2);
+/// # }
+/// ```
+pub struct Observed<S, T: Item> {
+    inner: S,
+    observers: Observers<T>,
+}
+
+impl<S, T: Item> Observed<S, T> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            observers: Observers { next_id: 0, list: Vec::new() },
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Call `f` on every later change, `f` is kept by the wrapper until
+    /// [`unobserve`](Self::unobserve) so it can not borrow locals
+    pub fn observe(&mut self, mut f: impl FnMut(&Event<'_, T>) + 'static) -> ObserverId {
+        self.register(Box::new(move |event| {
+            f(event);
+            true
+        }))
+    }
+
+    fn register(&mut self, callback: Callback<T>) -> ObserverId {
+        let id = ObserverId(self.observers.next_id);
+        self.observers.next_id += 1;
+        self.observers.list.push((id, callback));
+        id
+    }
+
+    /// Remove an observer, returns `false` if it was already removed
+    pub fn unobserve(&mut self, id: ObserverId) -> bool {
+        let len = self.observers.list.len();
+        self.observers.list.retain(|(observer, _)| *observer != id);
+        len != self.observers.list.len()
+    }
+
+    /// Receive every later change as an [`OwnedEvent`], cloning the items.
+    ///
+    /// The channel is unregistered at the first change after the receiver is dropped.
+    pub fn subscribe(&mut self) -> mpsc::Receiver<OwnedEvent<T>>
+    where
+        T: Clone + 'static,
+        T::Id: Clone,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.register(Box::new(move |event| sender.send(event.to_owned()).is_ok()));
+        receiver
+    }
+}
+
+impl<S: Transactional<T>, T: Item> Observed<S, T>
+where
+    T::Id: Clone + Hash + Eq,
+{
+    /// Add `value`, returns `false` and drops `value` when an equal item exists
+    pub fn insert(&mut self, value: T) -> bool {
+        let id = value.to_id();
+        let inserted = self.inner.__insert(value);
+        if inserted {
+            let item = self.inner.__get(&id).unwrap();
+            self.observers.notify(&Event::Inserted(item));
+        }
+        inserted
+    }
+
+    /// Add `value`, replacing and returning the existing equal item
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        let id = value.to_id();
+        let old = self.inner.__replace(value);
+        let new = self.inner.__get(&id).unwrap();
+        match old {
+            Some(old) => {
+                let event = Event::Replaced { old, new };
+                self.observers.notify(&event);
+                let Event::Replaced { old, .. } = event else { unreachable!() };
+                Some(old)
+            }
+            None => {
+                self.observers.notify(&Event::Inserted(new));
+                None
+            }
+        }
+    }
+
+    /// Remove and return the item equal to `value`
+    pub fn remove<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let (_, item) = self.inner.__remove(value)?;
+        let event = Event::Removed(item);
+        self.observers.notify(&event);
+        let Event::Removed(item) = event else { unreachable!() };
+        Some(item)
+    }
+
+    /// Mutable access to the item equal to `value`,
+    /// [`Event::Modified`] is sent when the guard is dropped
+    pub fn get_mut<Q>(&mut self, value: &Q) -> Option<RefMut<'_, T>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let item = self.inner.get_mut(value)?;
+        Some(RefMut { item, observers: &mut self.observers })
+    }
+
+    /// Apply `f` to the item equal to `value`, then send [`Event::Modified`]
+    pub fn modify<Q, R>(
+        &mut self,
+        value: &Q,
+        f: impl FnOnce(&mut T::IdReadonlyItem) -> R,
+    ) -> Option<R>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let mut item = self.get_mut(value)?;
+        Some(f(&mut item))
+    }
+
+    /// Apply `f` to every item, sending [`Event::Modified`] after each call
+    pub fn for_each_mut(&mut self, mut f: impl FnMut(&mut T::IdReadonlyItem)) {
+        for item in self.inner.iter_mut() {
+            f(item);
+            self.observers.notify(&Event::Modified(item.id()));
+        }
+    }
+}
+
+impl<S, T: Item> Deref for Observed<S, T> {
+    type Target = S;
+    fn deref(&self) -> &S {
+        &self.inner
+    }
+}
+
+/// Mutable access to an item of [`Observed`], only the non-id fields can be written
+pub struct RefMut<'a, T: Item> {
+    item: &'a mut T::IdReadonlyItem,
+    observers: &'a mut Observers<T>,
+}
+
+impl<T: Item> Deref for RefMut<'_, T> {
+    type Target = T::IdReadonlyItem;
+    #[inline]
+    fn deref(&self) -> &T::IdReadonlyItem {
+        self.item
+    }
+}
+
+impl<T: Item> DerefMut for RefMut<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T::IdReadonlyItem {
+        self.item
+    }
+}
+
+impl<T: Item> Drop for RefMut<'_, T> {
+    fn drop(&mut self) {
+        self.observers.notify(&Event::Modified(self.item.id()));
+    }
+}
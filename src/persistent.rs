@@ -0,0 +1,462 @@
+//! Persistent set with structural sharing
+
+use core::{
+    borrow::Borrow,
+    fmt,
+    hash::{BuildHasher, Hash},
+    mem,
+};
+use std::{hash::RandomState, sync::Arc};
+
+use crate::{Item, MutSetExt};
+
+const BITS: u32 = 5;
+const MASK: u64 = (1 << BITS) - 1;
+
+/// Hash array mapped trie node, `entries` are ordered by their bit in `bitmap`
+#[derive(Clone)]
+struct Branch<T> {
+    bitmap: u32,
+    entries: Vec<Entry<T>>,
+}
+
+#[derive(Clone)]
+enum Entry<T> {
+    Leaf(u64, T),
+    /// At least two items whose whole hashes are the same
+    Collision(u64, Arc<Vec<T>>),
+    Sub(Arc<Branch<T>>),
+}
+
+impl<T> Entry<T> {
+    fn hash(&self) -> u64 {
+        match self {
+            Self::Leaf(hash, _) | Self::Collision(hash, _) => *hash,
+            Self::Sub(_) => unreachable!(),
+        }
+    }
+}
+
+#[inline]
+const fn bit(hash: u64, shift: u32) -> u32 {
+    1 << ((hash >> shift) & MASK)
+}
+
+impl<T> Branch<T> {
+    const fn new() -> Self {
+        Self { bitmap: 0, entries: Vec::new() }
+    }
+
+    #[inline]
+    fn index(&self, bit: u32) -> usize {
+        (self.bitmap & (bit - 1)).count_ones() as usize
+    }
+
+    /// Branch holding both entries, which have different hashes
+    fn pair(shift: u32, a: Entry<T>, b: Entry<T>) -> Self {
+        let (bit_a, bit_b) = (bit(a.hash(), shift), bit(b.hash(), shift));
+        if bit_a == bit_b {
+            let sub = Self::pair(shift + BITS, a, b);
+            Self {
+                bitmap: bit_a,
+                entries: vec![Entry::Sub(Arc::new(sub))],
+            }
+        } else if bit_a < bit_b {
+            Self { bitmap: bit_a | bit_b, entries: vec![a, b] }
+        } else {
+            Self { bitmap: bit_a | bit_b, entries: vec![b, a] }
+        }
+    }
+
+    fn get<Q>(&self, hash: u64, shift: u32, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        let bit = bit(hash, shift);
+        if self.bitmap & bit == 0 {
+            return None;
+        }
+        match &self.entries[self.index(bit)] {
+            Entry::Leaf(h, item) => {
+                (*h == hash && item.borrow() == value).then_some(item)
+            }
+            Entry::Collision(h, items) if *h == hash => {
+                items.iter().find(|item| (*item).borrow() == value)
+            }
+            Entry::Collision(..) => None,
+            Entry::Sub(sub) => sub.get(hash, shift + BITS, value),
+        }
+    }
+}
+
+impl<T: Eq + Clone> Branch<T> {
+    /// Copy the nodes on the path to the item equal to `value`, it must exist
+    fn get_mut<Q>(&mut self, hash: u64, shift: u32, value: &Q) -> &mut T
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        let index = self.index(bit(hash, shift));
+        match &mut self.entries[index] {
+            Entry::Leaf(_, item) => item,
+            Entry::Collision(_, items) => Arc::make_mut(items)
+                .iter_mut()
+                .find(|item| (**item).borrow() == value)
+                .unwrap(),
+            Entry::Sub(sub) => Arc::make_mut(sub).get_mut(hash, shift + BITS, value),
+        }
+    }
+
+    /// Insert or replace `value`, returning the replaced item
+    fn put(&mut self, hash: u64, shift: u32, value: T) -> Option<T> {
+        let bit = bit(hash, shift);
+        let index = self.index(bit);
+        if self.bitmap & bit == 0 {
+            self.bitmap |= bit;
+            self.entries.insert(index, Entry::Leaf(hash, value));
+            return None;
+        }
+        let entry = &mut self.entries[index];
+        match entry {
+            Entry::Leaf(h, item) if *h == hash && *item == value => {
+                Some(mem::replace(item, value))
+            }
+            Entry::Collision(h, items) if *h == hash => {
+                let items = Arc::make_mut(items);
+                match items.iter_mut().find(|item| **item == value) {
+                    Some(item) => Some(mem::replace(item, value)),
+                    None => {
+                        items.push(value);
+                        None
+                    }
+                }
+            }
+            Entry::Sub(sub) => Arc::make_mut(sub).put(hash, shift + BITS, value),
+            Entry::Leaf(..) | Entry::Collision(..) => {
+                let old = self.entries.remove(index);
+                let new = match old {
+                    Entry::Leaf(h, item) if h == hash => {
+                        Entry::Collision(hash, Arc::new(vec![item, value]))
+                    }
+                    old => Entry::Sub(Arc::new(Self::pair(
+                        shift + BITS,
+                        old,
+                        Entry::Leaf(hash, value),
+                    ))),
+                };
+                self.entries.insert(index, new);
+                None
+            }
+        }
+    }
+
+    /// Remove the item equal to `value`, it must exist
+    fn remove<Q>(&mut self, hash: u64, shift: u32, value: &Q) -> T
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        let bit = bit(hash, shift);
+        let index = self.index(bit);
+        let removed = match &mut self.entries[index] {
+            Entry::Leaf(..) => {
+                self.bitmap &= !bit;
+                let Entry::Leaf(_, item) = self.entries.remove(index) else {
+                    unreachable!()
+                };
+                return item;
+            }
+            Entry::Collision(_, items) => {
+                let items = Arc::make_mut(items);
+                let position =
+                    items.iter().position(|item| item.borrow() == value).unwrap();
+                items.swap_remove(position)
+            }
+            Entry::Sub(sub) => Arc::make_mut(sub).remove(hash, shift + BITS, value),
+        };
+        // keep the trie canonical, a lone item moves up instead of keeping its own node
+        let entry = &mut self.entries[index];
+        match entry {
+            Entry::Collision(h, items) if items.len() == 1 => {
+                let item = Arc::make_mut(items).pop().unwrap();
+                *entry = Entry::Leaf(*h, item);
+            }
+            Entry::Sub(sub)
+                if sub.entries.len() == 1 && !matches!(sub.entries[0], Entry::Sub(_)) =>
+            {
+                *entry = Arc::make_mut(sub).entries.pop().unwrap();
+            }
+            _ => {}
+        }
+        removed
+    }
+}
+
+/// Hash set with O(1) `clone`, whose copies share their unchanged nodes.
+///
+/// Mutations copy only the nodes on the path to the touched items, so a copy
+/// handed to another thread is never affected by the later changes. The
+/// iteration order depends on the hashes only, so it is the same for all copies.
+pub struct PersistentSet<T, S = RandomState> {
+    root: Arc<Branch<T>>,
+    len: usize,
+    hash_builder: S,
+}
+
+impl<T> PersistentSet<T> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<T, S: Default> Default for PersistentSet<T, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<T, S: Clone> Clone for PersistentSet<T, S> {
+    /// Share the whole set, in O(1)
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+impl<T, S> PersistentSet<T, S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            root: Arc::new(Branch::new()),
+            len: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether both sets share the same root, then they are equal without comparing items
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.root, &other.root)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            stack: vec![self.root.entries.iter()],
+            collision: [].iter(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T: Item, S: BuildHasher> PersistentSet<T, S> {
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.root.get(self.hash_builder.hash_one(value), 0, value)
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get(value).is_some()
+    }
+}
+
+impl<T: Item + Clone, S: BuildHasher> PersistentSet<T, S> {
+    /// Add `value`, returns `false` and drops `value` when an equal item exists
+    pub fn insert(&mut self, value: T) -> bool {
+        if self.contains::<T>(&value) {
+            return false;
+        }
+        self.replace(value);
+        true
+    }
+
+    /// Add `value`, replacing and returning the existing equal item
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        let hash = self.hash_builder.hash_one(&value);
+        let old = Arc::make_mut(&mut self.root).put(hash, 0, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Remove and return the item equal to `value`
+    pub fn remove<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = self.hash_builder.hash_one(value);
+        // do not copy any node when there is nothing to remove
+        self.root.get(hash, 0, value)?;
+        self.len -= 1;
+        Some(Arc::make_mut(&mut self.root).remove(hash, 0, value))
+    }
+}
+
+impl<T: Item + Clone, S: BuildHasher> MutSetExt<T> for PersistentSet<T, S> {
+    type IterMut<'a>
+        = IterMut<'a, T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    /// Copy the nodes shared with other sets on the path to the item
+    fn get_mut<Q>(&mut self, value: &Q) -> Option<&mut T::IdReadonlyItem>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = self.hash_builder.hash_one(value);
+        self.root.get(hash, 0, value)?;
+        Some(Arc::make_mut(&mut self.root).get_mut(hash, 0, value).id_readonly())
+    }
+
+    /// Copy every node shared with other sets
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        IterMut {
+            stack: vec![Arc::make_mut(&mut self.root).entries.iter_mut()],
+            collision: [].iter_mut(),
+            len: self.len,
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, S> crate::ParMutSetExt<T> for PersistentSet<T, S>
+where
+    T: Item + Clone + Sync,
+    T::IdReadonlyItem: Send,
+    S: BuildHasher,
+{
+    type ParIterMut<'a>
+        = rayon::vec::IntoIter<&'a mut T::IdReadonlyItem>
+    where
+        Self: 'a,
+        T: 'a;
+
+    /// Collect the items first, then split them between threads
+    fn par_iter_mut(&mut self) -> Self::ParIterMut<'_> {
+        rayon::iter::IntoParallelIterator::into_par_iter(
+            self.iter_mut().collect::<Vec<_>>(),
+        )
+    }
+}
+
+impl<T: Item + Clone, S: BuildHasher + Default> FromIterator<T> for PersistentSet<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::with_hasher(S::default());
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Item + Clone, S: BuildHasher> Extend<T> for PersistentSet<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'a, T, S> IntoIterator for &'a PersistentSet<T, S> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for PersistentSet<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'a, T> {
+    stack: Vec<core::slice::Iter<'a, Entry<T>>>,
+    collision: core::slice::Iter<'a, T>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.collision.next() {
+                self.len -= 1;
+                return Some(item);
+            }
+            match self.stack.last_mut()?.next() {
+                Some(Entry::Leaf(_, item)) => {
+                    self.len -= 1;
+                    return Some(item);
+                }
+                Some(Entry::Collision(_, items)) => self.collision = items.iter(),
+                Some(Entry::Sub(sub)) => self.stack.push(sub.entries.iter()),
+                None => _ = self.stack.pop(),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T: Item> {
+    stack: Vec<core::slice::IterMut<'a, Entry<T>>>,
+    collision: core::slice::IterMut<'a, T>,
+    len: usize,
+}
+
+impl<'a, T: Item + Clone> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T::IdReadonlyItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.collision.next() {
+                self.len -= 1;
+                return Some(item.id_readonly());
+            }
+            match self.stack.last_mut()?.next() {
+                Some(Entry::Leaf(_, item)) => {
+                    self.len -= 1;
+                    return Some(item.id_readonly());
+                }
+                Some(Entry::Collision(_, items)) => {
+                    self.collision = Arc::make_mut(items).iter_mut();
+                }
+                Some(Entry::Sub(sub)) => {
+                    self.stack.push(Arc::make_mut(sub).entries.iter_mut())
+                }
+                None => _ = self.stack.pop(),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T: Item + Clone> ExactSizeIterator for IterMut<'_, T> {}
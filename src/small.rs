@@ -0,0 +1,479 @@
+//! Set storing a few items inline, without allocation
+
+use core::{
+    borrow::Borrow,
+    fmt,
+    hash::{BuildHasher, Hash},
+    mem::{self, ManuallyDrop, MaybeUninit},
+    ptr, slice,
+};
+use indexmap::IndexSet;
+use std::hash::RandomState;
+
+use crate::{Item, MutSetExt};
+
+/// Set holding up to `N` items inline, found by comparing their ids one by one,
+/// and moving them to an `IndexSet` when one more is inserted.
+///
+/// Items are iterated in insertion order, removing an item shifts the later ones.
+/// The set does not go back inline once it has spilled.
+/// ``` rust
+/// use mut_set::{MutSetExt, small::SmallMutSet};
+///
+/// #[mut_set::derive::item]
+/// pub struct Attr {
+///     #[id]
+///     pub name: &'static str,
+///     pub value: i32,
+/// }
+/// # fn main() {
+/// let mut attrs = SmallMutSet::<Attr, 2>::new();
+/// attrs.insert(Attr { name: "width", value: 1 });
+/// attrs.insert(Attr { name: "height", value: 2 });
+/// attrs.get_mut(&"width").unwrap().value = 3;
+/// assert!(!attrs.spilled());
+/// attrs.insert(Attr { name: "depth", value: 4 });
+/// assert!(attrs.spilled());
+/// let names: Vec<_> = attrs.iter().map(|attr| attr.name).collect();
+/// assert_eq!(names, ["width", "height", "depth"]);
+/// # }
+/// ```
+pub struct SmallMutSet<T, const N: usize, S = RandomState> {
+    repr: Repr<T, N, S>,
+}
+
+enum Repr<T, const N: usize, S> {
+    Inline(Inline<T, N>),
+    Heap(IndexSet<T, S>),
+}
+
+/// The first `len` items are initialized
+struct Inline<T, const N: usize> {
+    len: usize,
+    items: [MaybeUninit<T>; N],
+}
+
+impl<T, const N: usize> Inline<T, N> {
+    const fn new() -> Self {
+        Self {
+            len: 0,
+            items: [const { MaybeUninit::uninit() }; N],
+        }
+    }
+
+    #[inline]
+    fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.items.as_ptr().cast(), self.len) }
+    }
+
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.items.as_mut_ptr().cast(), self.len) }
+    }
+
+    fn position<Q>(&self, value: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Eq,
+    {
+        self.as_slice()
+            .iter()
+            .position(|item| Borrow::<Q>::borrow(item) == value)
+    }
+
+    /// Caller checks that there is room left
+    fn push(&mut self, value: T) {
+        self.items[self.len].write(value);
+        self.len += 1;
+    }
+
+    /// Emptied before the items are dropped, so that a panicking `Drop` leaves
+    /// no item to drop again
+    fn clear(&mut self) {
+        let len = mem::replace(&mut self.len, 0);
+        let items =
+            ptr::slice_from_raw_parts_mut(self.items.as_mut_ptr().cast::<T>(), len);
+        unsafe { ptr::drop_in_place(items) }
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        let tail = self.len - index - 1;
+        self.len -= 1;
+        unsafe {
+            let hole = self.items.as_mut_ptr().add(index);
+            let value = hole.read().assume_init();
+            ptr::copy(hole.add(1), hole, tail);
+            value
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for Inline<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for Inline<T, N> {
+    fn clone(&self) -> Self {
+        let mut inline = Self::new();
+        for item in self.as_slice() {
+            inline.push(item.clone());
+        }
+        inline
+    }
+}
+
+impl<T, const N: usize, S> SmallMutSet<T, N, S> {
+    pub const fn new() -> Self {
+        Self { repr: Repr::Inline(Inline::new()) }
+    }
+
+    /// Whether the items have been moved to the heap
+    pub fn spilled(&self) -> bool {
+        matches!(self.repr, Repr::Heap(_))
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Inline(inline) => inline.len,
+            Repr::Heap(set) => set.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Items in insertion order
+    pub fn iter(&self) -> Iter<'_, T> {
+        match &self.repr {
+            Repr::Inline(inline) => Iter::Inline(inline.as_slice().iter()),
+            Repr::Heap(set) => Iter::Heap(set.iter()),
+        }
+    }
+
+    /// Remove every item, keeping the allocation once spilled
+    pub fn clear(&mut self) {
+        match &mut self.repr {
+            Repr::Inline(inline) => inline.clear(),
+            Repr::Heap(set) => set.clear(),
+        }
+    }
+}
+
+impl<T: Item, const N: usize, S: BuildHasher + Default> SmallMutSet<T, N, S> {
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        match &self.repr {
+            Repr::Inline(inline) => inline.position(value).map(|i| &inline.as_slice()[i]),
+            Repr::Heap(set) => set.get(value),
+        }
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get(value).is_some()
+    }
+
+    /// Add `value`, returns `false` and drops `value` when an equal item exists
+    pub fn insert(&mut self, value: T) -> bool {
+        match &mut self.repr {
+            Repr::Inline(inline) => {
+                if inline.position::<T>(&value).is_some() {
+                    return false;
+                }
+                self.push(value);
+                true
+            }
+            Repr::Heap(set) => set.insert(value),
+        }
+    }
+
+    /// Add `value`, replacing and returning the existing equal item
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        match &mut self.repr {
+            Repr::Inline(inline) => match inline.position::<T>(&value) {
+                Some(index) => {
+                    Some(mem::replace(&mut inline.as_mut_slice()[index], value))
+                }
+                None => {
+                    self.push(value);
+                    None
+                }
+            },
+            Repr::Heap(set) => set.replace(value),
+        }
+    }
+
+    /// Append an item absent from the inline items, spilling them if they are full
+    fn push(&mut self, value: T) {
+        let Repr::Inline(inline) = &mut self.repr else { unreachable!() };
+        if inline.len < N {
+            inline.push(value);
+            return;
+        }
+        let heap = IndexSet::with_capacity_and_hasher(2 * N + 1, S::default());
+        let Repr::Inline(inline) = mem::replace(&mut self.repr, Repr::Heap(heap)) else {
+            unreachable!()
+        };
+        let Repr::Heap(set) = &mut self.repr else { unreachable!() };
+        set.extend(IntoIter::Inline(InlineIntoIter::new(inline)));
+        set.insert(value);
+    }
+
+    /// Remove and return the item equal to `value`, keeping the order of the others
+    pub fn remove<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        match &mut self.repr {
+            Repr::Inline(inline) => inline.position(value).map(|i| inline.remove(i)),
+            Repr::Heap(set) => set.shift_take(value),
+        }
+    }
+}
+
+impl<T: Item, const N: usize, S: BuildHasher + Default> MutSetExt<T>
+    for SmallMutSet<T, N, S>
+{
+    type IterMut<'a>
+        = IterMut<'a, T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn get_mut<Q>(&mut self, value: &Q) -> Option<&mut T::IdReadonlyItem>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        match &mut self.repr {
+            Repr::Inline(inline) => {
+                let index = inline.position(value)?;
+                Some(inline.as_mut_slice()[index].id_readonly())
+            }
+            Repr::Heap(set) => {
+                set.get(value).map(|item| unsafe { item.__unsafe_deref_mut() })
+            }
+        }
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        match &mut self.repr {
+            Repr::Inline(inline) => IterMut::Inline(inline.as_mut_slice().iter_mut()),
+            Repr::Heap(set) => IterMut::Heap(set.iter()),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, const N: usize, S> crate::ParMutSetExt<T> for SmallMutSet<T, N, S>
+where
+    T: Item + Sync,
+    T::IdReadonlyItem: Send,
+    S: BuildHasher + Default,
+{
+    type ParIterMut<'a>
+        = rayon::vec::IntoIter<&'a mut T::IdReadonlyItem>
+    where
+        Self: 'a,
+        T: 'a;
+
+    /// Collect the items first, then split them between threads
+    fn par_iter_mut(&mut self) -> Self::ParIterMut<'_> {
+        rayon::iter::IntoParallelIterator::into_par_iter(
+            self.iter_mut().collect::<Vec<_>>(),
+        )
+    }
+}
+
+impl<T, const N: usize, S> Default for SmallMutSet<T, N, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, const N: usize, S: Clone> Clone for SmallMutSet<T, N, S> {
+    fn clone(&self) -> Self {
+        let repr = match &self.repr {
+            Repr::Inline(inline) => Repr::Inline(inline.clone()),
+            Repr::Heap(set) => Repr::Heap(set.clone()),
+        };
+        Self { repr }
+    }
+}
+
+impl<T: fmt::Debug, const N: usize, S> fmt::Debug for SmallMutSet<T, N, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Item, const N: usize, S: BuildHasher + Default> FromIterator<T>
+    for SmallMutSet<T, N, S>
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Item, const N: usize, S: BuildHasher + Default> Extend<T>
+    for SmallMutSet<T, N, S>
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T, const N: usize, S> IntoIterator for SmallMutSet<T, N, S> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+    fn into_iter(self) -> IntoIter<T, N> {
+        match self.repr {
+            Repr::Inline(inline) => IntoIter::Inline(InlineIntoIter::new(inline)),
+            Repr::Heap(set) => IntoIter::Heap(set.into_iter()),
+        }
+    }
+}
+
+impl<'a, T, const N: usize, S> IntoIterator for &'a SmallMutSet<T, N, S> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+pub enum Iter<'a, T> {
+    Inline(slice::Iter<'a, T>),
+    Heap(indexmap::set::Iter<'a, T>),
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self {
+            Self::Inline(iter) => iter.next(),
+            Self::Heap(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Inline(iter) => iter.size_hint(),
+            Self::Heap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+pub enum IterMut<'a, T> {
+    Inline(slice::IterMut<'a, T>),
+    Heap(indexmap::set::Iter<'a, T>),
+}
+
+impl<'a, T: Item> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T::IdReadonlyItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Inline(iter) => iter.next().map(T::id_readonly),
+            // the set is borrowed mutably, as in `MutSetExt for IndexSet`
+            Self::Heap(iter) => {
+                iter.next().map(|item| unsafe { item.__unsafe_deref_mut() })
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Inline(iter) => iter.size_hint(),
+            Self::Heap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<T: Item> ExactSizeIterator for IterMut<'_, T> {}
+
+pub enum IntoIter<T, const N: usize> {
+    Inline(InlineIntoIter<T, N>),
+    Heap(indexmap::set::IntoIter<T>),
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self {
+            Self::Inline(iter) => iter.next(),
+            Self::Heap(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Inline(iter) => iter.size_hint(),
+            Self::Heap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+/// Items of a set that has not spilled, the ones in `next..end` are initialized
+pub struct InlineIntoIter<T, const N: usize> {
+    next: usize,
+    end: usize,
+    items: [MaybeUninit<T>; N],
+}
+
+impl<T, const N: usize> InlineIntoIter<T, N> {
+    fn new(inline: Inline<T, N>) -> Self {
+        let inline = ManuallyDrop::new(inline);
+        Self {
+            next: 0,
+            end: inline.len,
+            items: unsafe { ptr::read(&inline.items) },
+        }
+    }
+}
+
+impl<T, const N: usize> Iterator for InlineIntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.next == self.end {
+            return None;
+        }
+        self.next += 1;
+        Some(unsafe { self.items[self.next - 1].assume_init_read() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.next;
+        (len, Some(len))
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for InlineIntoIter<T, N> {}
+
+impl<T, const N: usize> Drop for InlineIntoIter<T, N> {
+    fn drop(&mut self) {
+        let rest = &mut self.items[self.next..self.end];
+        unsafe { ptr::drop_in_place(rest as *mut [MaybeUninit<T>] as *mut [T]) }
+    }
+}
@@ -0,0 +1,345 @@
+//! Set stored as a vector sorted by the ids, for small and read-heavy sets
+
+use alloc::{
+    boxed::Box,
+    vec::{self, Vec},
+};
+use core::{
+    borrow::Borrow,
+    fmt, iter, mem,
+    ops::{Bound, Range, RangeBounds},
+    slice,
+};
+
+use crate::Item;
+
+/// Set kept sorted by the `Ord` of the items, which compares their ids.
+///
+/// Lookups are binary searches and no hasher is needed, which is faster than
+/// hashing composite ids for sets of a few dozen items. Inserting and removing
+/// shift the following items.
+/// ``` rust
+/// use mut_set::sorted_vec::{SortedMutSetExt, SortedVecSet};
+///
+/// #[mut_set::derive::item]
+/// pub struct Cell {
+///     #[id]
+///     pub x: u32,
+///     pub area: f64,
+/// }
+/// # fn main() {
+/// let mut cells = SortedVecSet::from_unsorted(
+///     [3, 1, 2].map(|x| Cell { x, area: 1.0 }).into(),
+/// );
+/// cells.get_mut(&2).unwrap().area = 2.0;
+/// for cell in cells.range_mut(2..) {
+///     cell.area *= 10.0;
+/// }
+/// let areas: Vec<_> = cells.iter().map(|cell| cell.area).collect();
+/// assert_eq!(areas, [1.0, 20.0, 10.0]);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SortedVecSet<T> {
+    items: Vec<T>,
+}
+
+impl<T> SortedVecSet<T> {
+    pub const fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { items: Vec::with_capacity(capacity) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Items in ascending order
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<T> SortedVecSet<T> {
+    /// Index of the item whose `key` is `value`, or where it would be inserted
+    #[inline]
+    fn search_by<Q>(&self, value: &Q, key: impl Fn(&T) -> &Q) -> Result<usize, usize>
+    where
+        Q: ?Sized + Ord,
+    {
+        self.items.binary_search_by(|item| key(item).cmp(value))
+    }
+
+    /// Indexes of the items whose `key` is in `range`
+    fn indexes_by<Q, R>(&self, range: &R, key: impl Fn(&T) -> &Q) -> Range<usize>
+    where
+        Q: ?Sized + Ord,
+        R: RangeBounds<Q>,
+    {
+        let start = self.items.partition_point(|item| match range.start_bound() {
+            Bound::Included(start) => key(item) < start,
+            Bound::Excluded(start) => key(item) <= start,
+            Bound::Unbounded => false,
+        });
+        let end = self.items[start..].partition_point(|item| match range.end_bound() {
+            Bound::Included(end) => key(item) <= end,
+            Bound::Excluded(end) => key(item) < end,
+            Bound::Unbounded => true,
+        });
+        start..start + end
+    }
+}
+
+impl<T: Item> SortedVecSet<T> {
+    /// Items in ascending order, only the non-id fields can be written
+    pub fn as_mut_slice(&mut self) -> &mut [T::IdReadonlyItem] {
+        const {
+            assert!(
+                size_of::<T>() == size_of::<T::IdReadonlyItem>()
+                    && align_of::<T>() == align_of::<T::IdReadonlyItem>()
+            )
+        };
+        let len = self.items.len();
+        // `IdReadonlyItem` has the layout of `T`, as assumed by `__unsafe_deref_mut`
+        unsafe { slice::from_raw_parts_mut(self.items.as_mut_ptr().cast(), len) }
+    }
+}
+
+impl<T: Ord> SortedVecSet<T> {
+    /// Sort `items`, keeping the first one of equal items
+    pub fn from_unsorted(mut items: Vec<T>) -> Self {
+        // stable, so the first one stays in front of the equal ones
+        items.sort();
+        items.dedup_by(|later, first| later == first);
+        Self { items }
+    }
+
+    #[inline]
+    fn search<Q>(&self, value: &Q) -> Result<usize, usize>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.search_by(value, Borrow::borrow)
+    }
+
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.search(value).ok().map(|index| &self.items[index])
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.search(value).is_ok()
+    }
+
+    /// Add `value`, returns `false` and drops `value` when an equal item exists
+    pub fn insert(&mut self, value: T) -> bool {
+        match self.search::<T>(&value) {
+            Ok(_) => false,
+            Err(index) => {
+                self.items.insert(index, value);
+                true
+            }
+        }
+    }
+
+    /// Add `value`, replacing and returning the existing equal item
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        match self.search::<T>(&value) {
+            Ok(index) => Some(mem::replace(&mut self.items[index], value)),
+            Err(index) => {
+                self.items.insert(index, value);
+                None
+            }
+        }
+    }
+
+    /// Remove and return the item equal to `value`, keeping the order of the others
+    pub fn remove<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.search(value).ok().map(|index| self.items.remove(index))
+    }
+
+    /// Items whose ids are in `range`, in ascending order
+    pub fn range<Q, R>(&self, range: R) -> slice::Iter<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+        R: RangeBounds<Q>,
+    {
+        self.items[self.indexes_by(&range, Borrow::borrow)].iter()
+    }
+}
+
+/// `get_mut`/`iter_mut` of [`SortedVecSet`], which finds items by their `Ord`
+/// where [`MutSetExt`](crate::MutSetExt) hashes them.
+///
+/// A separate trait, as the lookups of [`MutSetExt`](crate::MutSetExt) are only
+/// bounded by `Hash + Eq`, and a binary search needs `Ord`. Code generic over
+/// both kinds of sets takes `iter_mut` from each trait.
+pub trait SortedMutSetExt<T: Item> {
+    type IterMut<'a>: Iterator<Item = &'a mut T::IdReadonlyItem>
+    where
+        Self: 'a,
+        T: 'a;
+    fn get_mut<Q>(&mut self, value: &Q) -> Option<&mut T::IdReadonlyItem>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord;
+    /// Items in ascending order, only the non-id fields can be written
+    fn iter_mut(&mut self) -> Self::IterMut<'_>;
+    /// Mutable items whose ids are in `range`, in ascending order
+    fn range_mut<Q, R>(&mut self, range: R) -> Self::IterMut<'_>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+        R: RangeBounds<Q>;
+}
+
+/// Extend [`SortedMutSetExt`] with `par_iter_mut`, for items sent between threads
+#[cfg(feature = "rayon")]
+pub trait ParSortedMutSetExt<T: Item>: SortedMutSetExt<T> {
+    type ParIterMut<'a>: rayon::iter::IndexedParallelIterator<Item = &'a mut T::IdReadonlyItem>
+    where
+        Self: 'a,
+        T: 'a;
+    /// Parallel version of `iter_mut`, in ascending order
+    fn par_iter_mut(&mut self) -> Self::ParIterMut<'_>;
+}
+
+type ReadonlyFn<'a, E, T> = fn(&'a mut E) -> &'a mut <T as Item>::IdReadonlyItem;
+
+fn boxed_readonly<T: Item>(item: &mut Box<T>) -> &mut T::IdReadonlyItem {
+    item.id_readonly()
+}
+
+/// Items are viewed one by one, `Box<T>` does not have the layout of `T`
+macro_rules! impl_sorted {
+    ($($elem:ty, $readonly:expr, |$item:ident| $key:expr;)*) => {
+        $(
+            impl<T: Item + Ord> SortedMutSetExt<T> for SortedVecSet<$elem> {
+                type IterMut<'a>
+                    = iter::Map<slice::IterMut<'a, $elem>, ReadonlyFn<'a, $elem, T>>
+                where
+                    Self: 'a,
+                    T: 'a;
+
+                fn get_mut<Q>(&mut self, value: &Q) -> Option<&mut T::IdReadonlyItem>
+                where
+                    T: Borrow<Q>,
+                    Q: ?Sized + Ord,
+                {
+                    let index = self.search_by(value, |$item| Borrow::<Q>::borrow($key)).ok()?;
+                    Some(($readonly)(&mut self.items[index]))
+                }
+
+                fn iter_mut(&mut self) -> Self::IterMut<'_> {
+                    self.items.iter_mut().map($readonly)
+                }
+
+                fn range_mut<Q, R>(&mut self, range: R) -> Self::IterMut<'_>
+                where
+                    T: Borrow<Q>,
+                    Q: ?Sized + Ord,
+                    R: RangeBounds<Q>,
+                {
+                    let indexes = self.indexes_by(&range, |$item| Borrow::<Q>::borrow($key));
+                    self.items[indexes].iter_mut().map($readonly)
+                }
+            }
+
+            #[cfg(feature = "rayon")]
+            impl<T> ParSortedMutSetExt<T> for SortedVecSet<$elem>
+            where
+                T: Item + Ord + Send,
+                T::IdReadonlyItem: Send,
+            {
+                type ParIterMut<'a>
+                    = rayon::iter::Map<rayon::slice::IterMut<'a, $elem>, ReadonlyFn<'a, $elem, T>>
+                where
+                    Self: 'a,
+                    T: 'a;
+
+                fn par_iter_mut(&mut self) -> Self::ParIterMut<'_> {
+                    use rayon::iter::ParallelIterator;
+                    rayon::iter::IntoParallelIterator::into_par_iter(&mut self.items[..])
+                        .map($readonly as ReadonlyFn<'_, $elem, T>)
+                }
+            }
+        )*
+    };
+}
+
+impl_sorted! {
+    T, T::id_readonly, |item| item;
+    Box<T>, boxed_readonly, |item| &**item;
+}
+
+impl<T: Ord> FromIterator<T> for SortedVecSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from_unsorted(iter.into_iter().collect())
+    }
+}
+
+impl<T: Ord> Extend<T> for SortedVecSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.items.extend(iter);
+        // stable and fast on two sorted runs, the existing items stay in front
+        // of the new equal ones and are kept
+        self.items.sort();
+        self.items.dedup_by(|later, first| later == first);
+    }
+}
+
+impl<T> Default for SortedVecSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IntoIterator for SortedVecSet<T> {
+    type Item = T;
+    type IntoIter = vec::IntoIter<T>;
+    fn into_iter(self) -> vec::IntoIter<T> {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SortedVecSet<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.items.iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SortedVecSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.items.iter()).finish()
+    }
+}
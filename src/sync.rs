@@ -0,0 +1,266 @@
+//! Concurrent item sets
+
+use core::{
+    borrow::Borrow,
+    fmt,
+    hash::{BuildHasher, Hash},
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+use indexmap::IndexSet;
+use std::{
+    hash::RandomState,
+    rc::Rc,
+    sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::Item;
+
+type Shard<T, S> = RwLock<IndexSet<T, S>>;
+
+/// A set shared between threads, split into shards by the hash of `T::Id`.
+///
+/// Each shard has its own lock, so writers to different shards do not block
+/// each other. Mutable access goes through [`RefMut`] guards, which deref to
+/// `T::IdReadonlyItem` and keep the id fields readonly.
+///
+/// A guard holds the lock of its shard: locking the same shard again from the
+/// thread holding the guard deadlocks. A panic while holding a guard does not
+/// poison the set, ids can not be modified so the shard stays consistent.
+pub struct ShardedSet<T: Item, S = RandomState> {
+    shards: Box<[Shard<T, S>]>,
+    hash_builder: S,
+}
+
+impl<T: Item> ShardedSet<T> {
+    /// Create a set with 4 shards per available core
+    pub fn new() -> Self {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_shards(cores * 4)
+    }
+
+    /// Create a set with at least `shards` shards, rounded up to a power of two
+    pub fn with_shards(shards: usize) -> Self {
+        Self::with_shards_and_hasher(shards, RandomState::new())
+    }
+}
+
+impl<T: Item> Default for ShardedSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Item, S: BuildHasher + Clone> ShardedSet<T, S> {
+    /// Create a set with at least `shards` shards, rounded up to a power of two
+    pub fn with_shards_and_hasher(shards: usize, hash_builder: S) -> Self {
+        let shards = (0..shards.max(1).next_power_of_two())
+            .map(|_| RwLock::new(IndexSet::with_hasher(hash_builder.clone())))
+            .collect();
+        Self { shards, hash_builder }
+    }
+}
+
+impl<T: Item, S: BuildHasher> ShardedSet<T, S> {
+    /// Number of shards
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn shard<Q>(&self, value: &Q) -> &Shard<T, S>
+    where
+        Q: ?Sized + Hash,
+    {
+        // every shard rehashes with the same hasher, and its table uses the low
+        // bits for the bucket and the top 7 bits as tag, so take the middle bits
+        let hash = self.hash_builder.hash_one(value);
+        let index = (hash >> 32) as usize & (self.shards.len() - 1);
+        &self.shards[index]
+    }
+
+    /// Add `value`, returns `false` and drops `value` when an equal item exists
+    pub fn insert(&self, value: T) -> bool {
+        write(self.shard(&value)).insert(value)
+    }
+
+    /// Add `value`, replacing and returning the existing equal item
+    pub fn replace(&self, value: T) -> Option<T> {
+        write(self.shard(&value)).replace(value)
+    }
+
+    /// Remove and return the item equal to `value`
+    pub fn remove<Q>(&self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        write(self.shard(value)).swap_take(value)
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        read(self.shard(value)).contains(value)
+    }
+
+    /// Shared access to the item equal to `value`, holding a read lock of its shard
+    pub fn get<Q>(&self, value: &Q) -> Option<Ref<'_, T, S>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let guard = read(self.shard(value));
+        let item = NonNull::from(guard.get(value)?);
+        Some(Ref { _guard: guard, item })
+    }
+
+    /// Mutable access to the item equal to `value`, holding a write lock of its shard
+    pub fn get_mut<Q>(&self, value: &Q) -> Option<RefMut<'_, T, S>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let guard = write(self.shard(value));
+        let item = NonNull::from(unsafe { guard.get(value)?.__unsafe_deref_mut() });
+        Some(RefMut { _guard: WriteGuard::Owned(guard), item })
+    }
+
+    /// Iterate over all items mutably, locking one shard at a time.
+    ///
+    /// A shard stays locked while the iterator or any guard it returned
+    /// for that shard is alive.
+    pub fn iter_mut(&self) -> IterMut<'_, T, S> {
+        IterMut {
+            shards: self.shards.iter(),
+            current: None,
+            index: 0,
+        }
+    }
+
+    /// Number of items, locking every shard in turn
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| read(shard).len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| read(shard).is_empty())
+    }
+
+    /// Collect all items into a single set
+    pub fn into_inner(self) -> IndexSet<T, S> {
+        let mut shards = self.shards.into_vec().into_iter();
+        let mut set = shards
+            .next()
+            .unwrap()
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        for shard in shards {
+            set.extend(shard.into_inner().unwrap_or_else(PoisonError::into_inner));
+        }
+        set
+    }
+}
+
+impl<T: Item, S: BuildHasher> fmt::Debug for ShardedSet<T, S>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut set = f.debug_set();
+        for shard in self.shards.iter() {
+            set.entries(read(shard).iter());
+        }
+        set.finish()
+    }
+}
+
+fn read<T, S>(shard: &Shard<T, S>) -> RwLockReadGuard<'_, IndexSet<T, S>> {
+    shard.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write<T, S>(shard: &Shard<T, S>) -> RwLockWriteGuard<'_, IndexSet<T, S>> {
+    shard.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Shared access to an item of [`ShardedSet`]
+pub struct Ref<'a, T: Item, S> {
+    _guard: RwLockReadGuard<'a, IndexSet<T, S>>,
+    item: NonNull<T>,
+}
+
+impl<T: Item, S> Deref for Ref<'_, T, S> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { self.item.as_ref() }
+    }
+}
+
+impl<T: Item + fmt::Debug, S> fmt::Debug for Ref<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+// only held to keep the shard locked
+#[allow(dead_code)]
+enum WriteGuard<'a, T, S> {
+    Owned(RwLockWriteGuard<'a, IndexSet<T, S>>),
+    Shared(Rc<RwLockWriteGuard<'a, IndexSet<T, S>>>),
+}
+
+/// Mutable access to an item of [`ShardedSet`], only the non-id fields can be written
+pub struct RefMut<'a, T: Item, S> {
+    _guard: WriteGuard<'a, T, S>,
+    item: NonNull<T::IdReadonlyItem>,
+}
+
+impl<T: Item, S> Deref for RefMut<'_, T, S> {
+    type Target = T::IdReadonlyItem;
+    #[inline]
+    fn deref(&self) -> &T::IdReadonlyItem {
+        unsafe { self.item.as_ref() }
+    }
+}
+
+impl<T: Item, S> DerefMut for RefMut<'_, T, S> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T::IdReadonlyItem {
+        unsafe { self.item.as_mut() }
+    }
+}
+
+impl<T: Item + fmt::Debug, S> fmt::Debug for RefMut<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&***self, f)
+    }
+}
+
+/// Iterator of [`ShardedSet::iter_mut`]
+pub struct IterMut<'a, T: Item, S> {
+    shards: core::slice::Iter<'a, Shard<T, S>>,
+    current: Option<Rc<RwLockWriteGuard<'a, IndexSet<T, S>>>>,
+    index: usize,
+}
+
+impl<'a, T: Item, S> Iterator for IterMut<'a, T, S> {
+    type Item = RefMut<'a, T, S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(guard) = &self.current
+                && let Some(item) = guard.get_index(self.index)
+            {
+                self.index += 1;
+                let item = NonNull::from(unsafe { item.__unsafe_deref_mut() });
+                return Some(RefMut { _guard: WriteGuard::Shared(guard.clone()), item });
+            }
+            // release the finished shard before locking the next one
+            self.current = None;
+            self.current = Some(Rc::new(write(self.shards.next()?)));
+            self.index = 0;
+        }
+    }
+}
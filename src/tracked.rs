@@ -0,0 +1,187 @@
+//! Change tracking over [`MutSetExt`] collections
+
+use core::{borrow::Borrow, hash::Hash, mem, ops::Deref};
+use indexmap::IndexSet;
+
+use crate::{Item, MutSetExt};
+
+/// Wrapper recording the ids of items mutably borrowed through it.
+///
+/// Read access goes through `Deref`, while [`inner_mut`](Self::inner_mut)
+/// gives untracked write access, e.g. to insert or remove items.
+///
+/// Only the ids are kept, in the order the items were first touched.
+/// ``` rust
+/// use mut_set::tracked::Tracked;
+///
+/// #[mut_set::derive::item]
+/// pub struct Cell {
+///     #[id]
+///     pub name: String,
+///     pub area: f64,
+/// }
+/// # fn main() {
+/// let mut cells = Tracked::new(indexmap::IndexSet::new());
+/// cells.inner_mut().insert(Cell { name: "a".into(), area: 1.0 });
+/// cells.inner_mut().insert(Cell { name: "b".into(), area: 1.0 });
+/// cells.get_mut(&"a".to_string()).unwrap().area = 2.0;
+/// // only recorded when the projected key changes
+/// cells.update(&"b".to_string(), |cell| cell.area, |cell| cell.area = 1.0);
+/// assert_eq!(cells.take_dirty().into_iter().collect::<Vec<_>>(), ["a"]);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tracked<S, T: Item> {
+    inner: S,
+    dirty: IndexSet<T::Id>,
+}
+
+impl<T: Item, S: Default> Default for Tracked<S, T> {
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
+
+impl<T: Item, S> Tracked<S, T> {
+    pub fn new(inner: S) -> Self {
+        Self { inner, dirty: IndexSet::new() }
+    }
+
+    /// Untracked mutable access to the collection
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Ids of the touched items, in the order they were first touched
+    pub fn dirty_ids(&self) -> &IndexSet<T::Id> {
+        &self.dirty
+    }
+
+    /// Return the touched ids and start over
+    pub fn take_dirty(&mut self) -> IndexSet<T::Id> {
+        mem::take(&mut self.dirty)
+    }
+
+    pub fn clear_dirty(&mut self) {
+        self.dirty.clear();
+    }
+}
+
+impl<T: Item, S> Tracked<S, T>
+where
+    T::Id: Clone + Hash + Eq,
+{
+    pub fn is_dirty<Q>(&self, value: &Q) -> bool
+    where
+        T::Id: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.dirty.contains(value)
+    }
+
+    /// Record an item as touched without borrowing it
+    pub fn mark_dirty(&mut self, item: &T) {
+        if !self.dirty.contains(item.id()) {
+            self.dirty.insert(item.to_id());
+        }
+    }
+}
+
+impl<T: Item, S: MutSetExt<T>> Tracked<S, T>
+where
+    T::Id: Clone + Hash + Eq,
+{
+    /// Same as [`MutSetExt::get_mut`], marking the item as dirty
+    pub fn get_mut<Q>(&mut self, value: &Q) -> Option<&mut T::IdReadonlyItem>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let item = self.inner.get_mut(value)?;
+        if !self.dirty.contains(item.id()) {
+            self.dirty.insert(item.to_id());
+        }
+        Some(item)
+    }
+
+    /// Same as [`MutSetExt::iter_mut`], marking every yielded item as dirty
+    pub fn iter_mut(&mut self) -> IterMut<'_, S::IterMut<'_>, T> {
+        IterMut {
+            inner: self.inner.iter_mut(),
+            dirty: &mut self.dirty,
+        }
+    }
+
+    /// Apply `f` to the item equal to `value`, marking it as dirty
+    /// only when `key` of the item differs before and after.
+    pub fn update<Q, K, R>(
+        &mut self,
+        value: &Q,
+        key: impl Fn(&T) -> K,
+        f: impl FnOnce(&mut T::IdReadonlyItem) -> R,
+    ) -> Option<R>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        K: PartialEq,
+    {
+        let item = self.inner.get_mut(value)?;
+        let before = key(item);
+        let out = f(item);
+        if before != key(item) && !self.dirty.contains(item.id()) {
+            self.dirty.insert(item.to_id());
+        }
+        Some(out)
+    }
+
+    /// Apply `f` to every item, marking as dirty the ones whose `key` changed
+    pub fn update_all<K>(
+        &mut self,
+        key: impl Fn(&T) -> K,
+        mut f: impl FnMut(&mut T::IdReadonlyItem),
+    ) where
+        K: PartialEq,
+    {
+        for item in self.inner.iter_mut() {
+            let before = key(item);
+            f(item);
+            if before != key(item) && !self.dirty.contains(item.id()) {
+                self.dirty.insert(item.to_id());
+            }
+        }
+    }
+}
+
+impl<T: Item, S> Deref for Tracked<S, T> {
+    type Target = S;
+    fn deref(&self) -> &S {
+        &self.inner
+    }
+}
+
+/// Iterator of [`Tracked::iter_mut`]
+pub struct IterMut<'a, I, T: Item> {
+    inner: I,
+    dirty: &'a mut IndexSet<T::Id>,
+}
+
+impl<'a, I, T> Iterator for IterMut<'a, I, T>
+where
+    I: Iterator<Item = &'a mut T::IdReadonlyItem>,
+    T: Item + 'a,
+    T::Id: Clone + Hash + Eq,
+{
+    type Item = &'a mut T::IdReadonlyItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        if !self.dirty.contains(item.id()) {
+            self.dirty.insert(item.to_id());
+        }
+        Some(item)
+    }
+}
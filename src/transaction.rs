@@ -0,0 +1,202 @@
+//! Batch mutations that are reverted on error or panic
+
+use core::{borrow::Borrow, hash::Hash, ops::Deref};
+use std::collections::HashSet;
+
+use crate::{Item, MutSetExt};
+
+/// Run a batch of mutations as one transaction, see [`Transactional::transaction`]
+pub trait Transactional<T: Item>: MutSetExt<T> + Sized {
+    /// Where a removed item has to be put back to restore the order
+    #[doc(hidden)]
+    type Position;
+
+    /// Run `f` with a [`Transaction`] over the set.
+    ///
+    /// When `f` returns `Err` or panics, every change made through the
+    /// transaction is undone, including the order of an `IndexSet`.
+    /// Items are cloned lazily: only those touched by `get_mut`, `iter_mut`,
+    /// `replace` or `remove` are saved, each at most once for `get_mut`/`iter_mut`.
+    /// ``` rust
+    /// use mut_set::Transactional;
+    ///
+    /// #[derive(Clone)]
+    /// #[mut_set::derive::item]
+    /// pub struct Cell {
+    ///     #[id]
+    ///     pub name: String,
+    ///     pub area: f64,
+    /// }
+    /// # fn main() {
+    /// let mut cells = indexmap::IndexSet::new();
+    /// cells.insert(Cell { name: "a".into(), area: 1.0 });
+    /// let result = cells.transaction(|tx| {
+    ///     tx.insert(Cell { name: "b".into(), area: -1.0 });
+    ///     tx.get_mut(&"a".to_string()).unwrap().area = 2.0;
+    ///     if tx.iter().any(|cell| cell.area < 0.0) {
+    ///         return Err("negative area");
+    ///     }
+    ///     Ok(())
+    /// });
+    /// assert_eq!(result, Err("negative area"));
+    /// assert_eq!(cells.len(), 1);
+    /// assert_eq!(cells[0].area, 1.0);
+    /// # }
+    /// ```
+    fn transaction<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        T: Clone,
+        T::Id: Clone + Hash + Eq,
+        F: FnOnce(&mut Transaction<'_, T, Self>) -> Result<R, E>,
+    {
+        let mut tx = Transaction { set: self, log: Vec::new(), saved: HashSet::new() };
+        let out = f(&mut tx)?;
+        tx.log.clear();
+        Ok(out)
+    }
+
+    /// Item equal to `value`, read without viewing it mutably
+    #[doc(hidden)]
+    fn __get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq;
+    /// Insert `value` unless an equal item exists
+    #[doc(hidden)]
+    fn __insert(&mut self, value: T) -> bool;
+    /// Insert or replace `value`, returning the replaced item
+    #[doc(hidden)]
+    fn __replace(&mut self, value: T) -> Option<T>;
+    /// Remove the item equal to `value`, returning where it was
+    #[doc(hidden)]
+    fn __remove<Q>(&mut self, value: &Q) -> Option<(Self::Position, T)>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq;
+    /// Remove the most recently inserted item, which is equal to `id`
+    #[doc(hidden)]
+    fn __undo_insert(&mut self, id: &T::Id)
+    where
+        T::Id: Hash + Eq;
+    /// Put back an item removed by `__remove`, the inverse operations
+    /// are applied in reverse order
+    #[doc(hidden)]
+    fn __undo_remove(&mut self, position: Self::Position, value: T);
+}
+
+enum Undo<T: Item, P> {
+    Inserted(T::Id),
+    Replaced(T),
+    Removed(P, T),
+}
+
+/// Mutable access to a set inside [`Transactional::transaction`].
+///
+/// Read access goes through `Deref`.
+pub struct Transaction<'a, T, S>
+where
+    T: Item + Clone,
+    T::Id: Clone + Hash + Eq,
+    S: Transactional<T>,
+{
+    set: &'a mut S,
+    log: Vec<Undo<T, S::Position>>,
+    /// Ids whose item was saved before being borrowed mutably, restoring
+    /// that first copy is enough as the later undo entries run before it.
+    saved: HashSet<T::Id>,
+}
+
+impl<T, S> Transaction<'_, T, S>
+where
+    T: Item + Clone,
+    T::Id: Clone + Hash + Eq,
+    S: Transactional<T>,
+{
+    #[inline]
+    fn save(log: &mut Vec<Undo<T, S::Position>>, saved: &mut HashSet<T::Id>, item: &T) {
+        if !saved.contains(item.id()) {
+            saved.insert(item.to_id());
+            log.push(Undo::Replaced(item.clone()));
+        }
+    }
+
+    /// Add `value`, returns `false` and drops `value` when an equal item exists
+    pub fn insert(&mut self, value: T) -> bool {
+        let id = value.to_id();
+        let inserted = self.set.__insert(value);
+        if inserted {
+            self.log.push(Undo::Inserted(id));
+        }
+        inserted
+    }
+
+    /// Add `value`, replacing and returning the existing equal item
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        let id = value.to_id();
+        let old = self.set.__replace(value);
+        self.log.push(match &old {
+            Some(old) => Undo::Replaced(old.clone()),
+            None => Undo::Inserted(id),
+        });
+        old
+    }
+
+    /// Remove and return the item equal to `value`
+    pub fn remove<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let (position, item) = self.set.__remove(value)?;
+        self.log.push(Undo::Removed(position, item.clone()));
+        Some(item)
+    }
+
+    /// Same as [`MutSetExt::get_mut`], saving the item first
+    pub fn get_mut<Q>(&mut self, value: &Q) -> Option<&mut T::IdReadonlyItem>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let item = self.set.get_mut(value)?;
+        Self::save(&mut self.log, &mut self.saved, item);
+        Some(item)
+    }
+
+    /// Same as [`MutSetExt::iter_mut`], saving every yielded item first
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T::IdReadonlyItem> {
+        let Self { set, log, saved } = self;
+        set.iter_mut().inspect(|item| Self::save(log, saved, item))
+    }
+}
+
+impl<T, S> Deref for Transaction<'_, T, S>
+where
+    T: Item + Clone,
+    T::Id: Clone + Hash + Eq,
+    S: Transactional<T>,
+{
+    type Target = S;
+    fn deref(&self) -> &S {
+        self.set
+    }
+}
+
+impl<T, S> Drop for Transaction<'_, T, S>
+where
+    T: Item + Clone,
+    T::Id: Clone + Hash + Eq,
+    S: Transactional<T>,
+{
+    fn drop(&mut self) {
+        while let Some(undo) = self.log.pop() {
+            match undo {
+                Undo::Inserted(id) => self.set.__undo_insert(&id),
+                Undo::Replaced(item) => {
+                    self.set.__replace(item);
+                }
+                Undo::Removed(position, item) => self.set.__undo_remove(position, item),
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+// cargo expand --manifest-path ./tests/Cargo.toml cache_hash
+use mut_set::{MutSetExt, PrehashedIndexSet};
+use std::hash::{BuildHasher, RandomState};
+
+/// Derives below `item`, so that they see the cached hash
+#[mut_set::derive::item(cache_hash)]
+#[derive(Debug, Clone)]
+pub(super) struct Pin {
+    #[id]
+    pub cell: String,
+    pub cap: f64,
+    #[id]
+    pub name: String,
+}
+
+#[test]
+fn cached() {
+    let pin = Pin::new("and2".into(), 1.0, "a".into());
+    let id = PinId::new("and2".into(), "a".into());
+    let state = RandomState::new();
+    assert_eq!(state.hash_one(&pin), state.hash_one(&id));
+    assert_eq!(state.hash_one(&id), state.hash_one(PinId::from(&pin)));
+    assert_eq!(state.hash_one(&id), state.hash_one(id.clone()));
+    assert_eq!(
+        state.hash_one(&id),
+        state.hash_one(PinId::from(("and2".to_string(), "a".to_string())))
+    );
+    assert_ne!(
+        state.hash_one(&id),
+        state.hash_one(PinId::new("and2".into(), "b".into()))
+    );
+
+    let mut set: PrehashedIndexSet<Pin> = ["a", "b", "y"]
+        .into_iter()
+        .map(|name| Pin::new("and2".into(), 1.0, name.into()))
+        .collect();
+    assert!(!set.insert(pin));
+    set.get_mut(&id).unwrap().cap = 2.0;
+    for pin in set.iter_mut() {
+        pin.cap *= 2.0;
+    }
+    let caps: Vec<_> = set.iter().map(|pin| pin.cap).collect();
+    assert_eq!(caps, [4.0, 2.0, 2.0]);
+    assert_eq!(set.hasher().hash_one(&set[0]), set.hasher().hash_one(&id));
+    assert!(set.shift_remove(&PinId::new("and2".into(), "y".into())));
+    assert_eq!(set.len(), 2);
+    assert_eq!(format!("{id}"), r#"("and2", "a")"#);
+}
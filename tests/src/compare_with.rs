@@ -0,0 +1,82 @@
+// cargo expand --manifest-path ./tests/Cargo.toml compare_with
+use core::{cmp::Ordering, hash::Hasher};
+
+fn hash_case_insensitive<H: Hasher>(val: &str, state: &mut H) {
+    for b in val.bytes() {
+        state.write_u8(b.to_ascii_lowercase());
+    }
+    state.write_u8(0xff);
+}
+
+fn eq_case_insensitive(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+/// Case-insensitive order, ties broken by nothing
+fn cmp_case_insensitive(a: &str, b: &str) -> Ordering {
+    a.bytes()
+        .map(|b| b.to_ascii_lowercase())
+        .cmp(b.bytes().map(|b| b.to_ascii_lowercase()))
+}
+
+const RESOLUTION: f64 = 1e-3;
+
+fn hash_quantized<H: Hasher>(val: &f64, state: &mut H) {
+    state.write_i64((val / RESOLUTION).round() as i64);
+}
+
+fn eq_quantized(a: &f64, b: &f64) -> bool {
+    (a / RESOLUTION).round() == (b / RESOLUTION).round()
+}
+
+fn cmp_quantized(a: &f64, b: &f64) -> Ordering {
+    (a / RESOLUTION).round().total_cmp(&(b / RESOLUTION).round())
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id(hash_with = hash_case_insensitive, eq_with = eq_case_insensitive, cmp_with = cmp_case_insensitive)]
+    pub name: String,
+    #[id(hash_with = hash_quantized, eq_with = eq_quantized, cmp_with = cmp_quantized)]
+    pub value: f64,
+    pub ctx: usize,
+}
+
+fn bad_hash<H: Hasher>(val: &str, state: &mut H) {
+    state.write(val.as_bytes());
+}
+
+#[mut_set::derive::item]
+pub(super) struct BadItem {
+    #[id(hash_with = bad_hash, eq_with = eq_case_insensitive, cmp_with = cmp_case_insensitive)]
+    pub name: String,
+    #[id]
+    pub ctx: usize,
+}
+
+#[test]
+fn test() {
+    use mut_set::MutSetExt;
+
+    let mut set = indexmap::IndexSet::new();
+    set.insert(MyItem { name: "Foo".into(), value: 1.0, ctx: 0 });
+    assert!(!set.insert(MyItem { name: "FOO".into(), value: 1.0001, ctx: 1 }));
+    set.insert(MyItem { name: "bar".into(), value: 1.0, ctx: 2 });
+    set.insert(MyItem { name: "Foo".into(), value: 2.0, ctx: 3 });
+    assert_eq!(set.len(), 3);
+    set.get_mut(&MyItemId::new("foo".into(), 0.9999)).unwrap().ctx = 4;
+    assert_eq!(set[0].ctx, 4);
+    set.sort();
+    let names: Vec<_> = set.iter().map(|item| (item.name.as_str(), item.value)).collect();
+    assert_eq!(names, [("bar", 1.0), ("Foo", 1.0), ("Foo", 2.0)]);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "values that are equal must hash equally")]
+fn inconsistent_hash() {
+    let a = BadItem { name: "Foo".into(), ctx: 0 };
+    let b = BadItem { name: "foo".into(), ctx: 0 };
+    let _ = a == b;
+}
@@ -0,0 +1,58 @@
+// cargo expand --manifest-path ./tests/Cargo.toml compute_id
+use indexmap::IndexSet;
+use mut_set::MutSetExt;
+
+/// Libraries are case-insensitive
+#[derive(Debug)]
+#[mut_set::derive::item(compute_id = |id| (id.lib.to_lowercase(), id.name.as_str()))]
+pub(super) struct Cell {
+    #[id(source)]
+    pub lib: String,
+    #[id(source)]
+    pub name: String,
+    pub area: f64,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item(compute_id = PointId::bits)]
+pub(super) struct Point {
+    #[id(source)]
+    pub x: f64,
+    #[id(source)]
+    pub y: f64,
+    pub label: &'static str,
+}
+
+impl PointId {
+    fn bits(&self) -> (u64, u64) {
+        (self.x.to_bits(), self.y.to_bits())
+    }
+}
+
+#[test]
+fn closure() {
+    let mut cells: IndexSet<Cell> = IndexSet::new();
+    assert!(cells.insert(Cell { lib: "STD".into(), name: "and2".into(), area: 1.0 }));
+    assert!(!cells.insert(Cell { lib: "std".into(), name: "and2".into(), area: 2.0 }));
+    assert!(cells.insert(Cell { lib: "std".into(), name: "or2".into(), area: 2.0 }));
+    let id = CellId::new("Std".into(), "and2".into());
+    cells.get_mut(&id).unwrap().area = 3.0;
+    assert_eq!(cells[0].area, 3.0);
+    // the fields are kept as they are
+    assert_eq!(cells[0].lib, "STD");
+    assert_eq!(id, CellId::new("std".into(), "and2".into()));
+    assert!(id < CellId::new("std".into(), "or2".into()));
+    assert_eq!(format!("{id}"), r#"("Std", "and2")"#);
+}
+
+#[test]
+fn method() {
+    let mut points: IndexSet<Point> = [(0.0, 1.0), (1.0, 0.0)]
+        .into_iter()
+        .map(|(x, y)| Point { x, y, label: "" })
+        .collect();
+    points.get_mut(&PointId::new(1.0, 0.0)).unwrap().label = "east";
+    assert_eq!(points[1].label, "east");
+    assert!(points.get(&PointId::new(1.0, 1.0)).is_none());
+    assert!(PointId::new(0.0, 1.0) < PointId::new(1.0, 0.0));
+}
@@ -0,0 +1,67 @@
+// cargo expand --manifest-path ./tests/Cargo.toml flatten
+use indexmap::IndexSet;
+use mut_set::{Item, MutSetExt};
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(super) struct CellKey {
+    pub lib: String,
+    pub name: String,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Cell {
+    pub area: f64,
+    #[id(flatten)]
+    pub key: CellKey,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Pin {
+    #[id(flatten(lib: String, name: String))]
+    pub cell: CellKey,
+    #[id]
+    pub pin: String,
+    pub cap: f64,
+}
+
+fn key(lib: &str, name: &str) -> CellKey {
+    CellKey { lib: lib.into(), name: name.into() }
+}
+
+#[test]
+fn key_struct() {
+    let mut cells: IndexSet<Cell> = ["and2", "or2"]
+        .into_iter()
+        .map(|name| Cell { area: 1.0, key: key("std", name) })
+        .collect();
+    // `CellId` is `CellKey`
+    let id: CellId = key("std", "or2");
+    cells.get_mut(&id).unwrap().area = 2.0;
+    assert_eq!(cells.get(&id).unwrap().area, 2.0);
+    assert_eq!(cells[1].id(), &id);
+    assert_eq!(CellKey::from(&cells[1]), id);
+    assert_eq!(cells[1].key.name, "or2");
+    assert!(cells.get(&key("lvt", "or2")).is_none());
+}
+
+#[test]
+fn inlined() {
+    let mut pins: IndexSet<Pin> = [("and2", "A"), ("and2", "B"), ("or2", "A")]
+        .into_iter()
+        .map(|(name, pin)| Pin { cell: key("std", name), pin: pin.into(), cap: 1.0 })
+        .collect();
+    // looked up by the parts of the key
+    let id = PinId::new("std".into(), "and2".into(), "B".into());
+    pins.get_mut(&id).unwrap().cap = 2.0;
+    assert_eq!(pins.get(&id).unwrap().cap, 2.0);
+    assert_eq!(pins[1].id(), &id);
+    assert_eq!(pins[1].id().name, "and2");
+    assert_eq!(PinId::from(&pins[2]), PinId::new("std".into(), "or2".into(), "A".into()));
+    assert!(
+        pins.get(&PinId::new("lvt".into(), "and2".into(), "B".into()))
+            .is_none()
+    );
+}
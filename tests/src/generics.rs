@@ -0,0 +1,183 @@
+// cargo expand --manifest-path ./tests/Cargo.toml generics
+use core::hash::Hash;
+use indexmap::IndexSet;
+use mut_set::{Item, MutSetExt};
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Token<'src> {
+    #[id]
+    pub text: &'src str,
+    pub kind: u8,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Span<'src> {
+    #[id]
+    pub file: &'src str,
+    #[id]
+    pub line: u32,
+    pub note: &'src str,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Bus<const N: usize> {
+    #[id]
+    pub name: String,
+    #[id]
+    pub bits: [u8; N],
+    pub load: f64,
+}
+
+/// `T` is not part of the id
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Wire<T> {
+    #[id]
+    pub name: String,
+    #[id]
+    pub index: u32,
+    pub load: T,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Node<'a, T: ?Sized, U>
+where
+    T: Hash + Ord,
+    U: Clone,
+{
+    #[id]
+    pub key: &'a T,
+    #[id]
+    pub index: u32,
+    pub value: U,
+}
+
+#[test]
+fn lifetime() {
+    let source = String::from("let x = 1");
+    let mut tokens: IndexSet<Token<'_>> =
+        source.split(' ').map(|text| Token { text, kind: 0 }).collect();
+    let id: TokenId<'_> = &source[4..5];
+    tokens.get_mut(&id).unwrap().kind = 1;
+    assert_eq!(tokens.get(&"x").unwrap().kind, 1);
+
+    let mut spans: IndexSet<Span<'_>> = IndexSet::new();
+    spans.insert(Span { file: &source, line: 1, note: "" });
+    let id: SpanId<'_> = SpanId::new(&source, 1);
+    spans.get_mut(&id).unwrap().note = "first";
+    assert_eq!(spans[0].note, "first");
+    assert_eq!(SpanId::from(&spans[0]), id);
+    assert_eq!(<(&str, u32)>::from(id), ("let x = 1", 1));
+}
+
+#[test]
+fn const_generic() {
+    let mut buses: IndexSet<Bus<2>> = IndexSet::new();
+    buses.insert(Bus { name: "a".into(), bits: [0, 1], load: 1.0 });
+    buses.insert(Bus { name: "a".into(), bits: [1, 0], load: 1.0 });
+    let id: BusId<2> = BusId::new("a".into(), [1, 0]);
+    buses.get_mut(&id).unwrap().load = 2.0;
+    assert_eq!(buses[1].load, 2.0);
+    assert!(BusId::new("a".into(), [0, 1]) < id);
+    assert_eq!(format!("{id}"), r#"("a", [1, 0])"#);
+}
+
+#[test]
+fn unused_generic() {
+    let mut wires: IndexSet<Wire<Vec<f64>>> = IndexSet::new();
+    wires.insert(Wire { name: "w".into(), index: 0, load: vec![] });
+    // `WireId` has no parameter
+    let id: WireId = WireId::new("w".into(), 0);
+    wires.get_mut(&id).unwrap().load.push(1.0);
+    assert_eq!(wires[0].load, [1.0]);
+    assert_eq!(<(String, u32)>::from(id.clone()), ("w".to_string(), 0));
+}
+
+#[test]
+fn where_clause() {
+    let keys: [&str; 2] = ["b", "a"];
+    let mut nodes: IndexSet<Node<'_, str, Vec<u8>>> = keys
+        .into_iter()
+        .map(|key| Node { key, index: 0, value: vec![] })
+        .collect();
+    let id: NodeId<'_, str> = NodeId::new("a", 0);
+    nodes.get_mut(&id).unwrap().value.push(1);
+    assert_eq!(nodes[1].value, [1]);
+    assert!(id < NodeId::new("b", 0));
+    assert_eq!(format!("{id:?}"), r#"NodeId { key: "a", index: 0 }"#);
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Entry<K> {
+    #[id]
+    pub key: K,
+    pub value: u32,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Pair<K, V> {
+    #[id]
+    pub key: K,
+    #[id]
+    pub index: u32,
+    pub value: V,
+}
+
+#[mut_set::derive::item(cache_hash)]
+#[derive(Debug)]
+pub(super) struct Cached<K> {
+    #[id]
+    pub key: K,
+    #[id]
+    pub index: u32,
+    pub value: f64,
+}
+
+/// Registry generic over the key type, without spelling out the id bounds
+struct Registry<K> {
+    entries: IndexSet<Entry<K>>,
+}
+
+impl<K: Hash + Ord> Registry<K> {
+    fn bump(&mut self, key: K) -> u32 {
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.value += 1;
+            entry.value
+        } else {
+            self.entries.insert(Entry { key, value: 0 });
+            0
+        }
+    }
+}
+
+#[test]
+fn generic_id() {
+    let mut registry = Registry { entries: IndexSet::new() };
+    assert_eq!(registry.bump("a"), 0);
+    assert_eq!(registry.bump("a"), 1);
+    assert_eq!(registry.bump("b"), 0);
+    let key: &EntryId<&str> = registry.entries[0].id();
+    assert_eq!(*key, "a");
+
+    let mut pairs: IndexSet<Pair<String, Vec<u8>>> = IndexSet::new();
+    pairs.insert(Pair { key: "a".into(), index: 0, value: vec![] });
+    pairs.insert(Pair { key: "a".into(), index: 1, value: vec![] });
+    let id: PairId<String> = PairId::new("a".into(), 1);
+    pairs.get_mut(&id).unwrap().value.push(1);
+    assert_eq!(pairs[1].value, [1]);
+    assert!(PairId::new("a".to_string(), 0) < id);
+    assert_eq!(PairId::from(&pairs[1]), id.clone());
+    assert_eq!(PairId::from(("a".to_string(), 1)), id);
+    assert_eq!(format!("{id}"), r#"("a", 1)"#);
+
+    let mut cached: mut_set::PrehashedIndexSet<Cached<u8>> =
+        (0..3).map(|index| Cached::new(7, index, 1.0)).collect();
+    cached.get_mut(&CachedId::new(7, 2)).unwrap().value = 2.0;
+    assert_eq!(cached[2].value, 2.0);
+}
@@ -0,0 +1,91 @@
+// cargo expand --manifest-path ./tests/Cargo.toml handle
+use mut_set::{
+    MutSetExt,
+    handle::{Handle, HandleSet},
+};
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Node {
+    #[id]
+    pub name: String,
+    #[id]
+    pub layer: usize,
+    pub edges: Vec<Handle<Node>>,
+}
+
+impl Node {
+    fn new(name: &str) -> Self {
+        Self { name: name.into(), layer: 0, edges: Vec::new() }
+    }
+}
+
+#[test]
+fn handles() {
+    let mut set = HandleSet::new();
+    let a = set.insert(Node::new("a"));
+    let b = set.insert(Node::new("b"));
+    let c = set.insert(Node::new("c"));
+    set.get_mut_by_handle(a).unwrap().edges.extend([b, c]);
+    set.get_mut_by_handle(b).unwrap().edges.push(c);
+    assert_eq!(set.len(), 3);
+    assert_eq!(set.handle_of(&NodeId::new("b".into(), 0)), Some(b));
+    assert_eq!(set.handle_of(&NodeId::new("b".into(), 1)), None);
+
+    // replacing keeps the handle
+    let (handle, old) = set.replace(Node::new("c"));
+    assert_eq!(handle, c);
+    assert!(old.is_some());
+
+    assert_eq!(set.remove(b).unwrap().name, "b");
+    assert!(set.get(b).is_none());
+    assert!(set.remove(b).is_none());
+    // the slot is reused, the stale handle stays dead
+    let d = set.insert(Node::new("d"));
+    assert_eq!(d.index(), b.index());
+    assert_ne!(d, b);
+    assert!(set.get_mut_by_handle(b).is_none());
+    let names: Vec<_> = set
+        .get(a)
+        .unwrap()
+        .edges
+        .iter()
+        .map(|&h| set.get(h).map(|n| &n.name))
+        .collect();
+    assert_eq!(names, [None, Some(&"c".to_string())]);
+
+    assert!(set.take(&NodeId::new("c".into(), 0)).is_some());
+    assert!(!set.contains(c));
+    assert_eq!(set.len(), 2);
+    set.clear();
+    assert!(set.is_empty() && !set.contains(a));
+}
+
+#[test]
+fn mut_set_ext() {
+    let mut set: HandleSet<_> = ["a", "b", "c"].into_iter().map(Node::new).collect();
+    set.iter_mut().for_each(|node| node.edges.clear());
+    let a = set.handle_of(&NodeId::new("a".into(), 0)).unwrap();
+    let c = set.handle_of(&NodeId::new("c".into(), 0)).unwrap();
+    set.get_mut(&NodeId::new("b".into(), 0)).unwrap().edges.push(a);
+    set.remove(a);
+    assert_eq!(set.iter_mut().count(), 2);
+    assert_eq!(set.iter().map(|(h, _)| h).collect::<Vec<_>>().last(), Some(&c));
+}
+
+#[test]
+fn par_iter_mut() {
+    use mut_set::ParMutSetExt;
+    use rayon::iter::ParallelIterator;
+    let mut set: HandleSet<_> =
+        (0..100).map(|layer| Node { layer, ..Node::new("n") }).collect();
+    for layer in (0..100).step_by(2) {
+        set.take(&NodeId::new("n".into(), layer));
+    }
+    set.par_iter_mut()
+        .for_each(|node| node.edges = Vec::with_capacity(node.layer));
+    assert!(
+        set.iter()
+            .all(|(_, node)| node.edges.capacity() >= node.layer && node.layer % 2 == 1)
+    );
+}
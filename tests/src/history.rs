@@ -0,0 +1,98 @@
+// cargo expand --manifest-path ./tests/Cargo.toml history
+use mut_set::history::History;
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item]
+pub(super) struct Cell {
+    #[id]
+    pub name: String,
+    #[id]
+    pub layer: usize,
+    pub area: usize,
+}
+
+fn cell(name: &str, area: usize) -> Cell {
+    Cell { name: name.into(), layer: 0, area }
+}
+
+fn id(name: &str) -> CellId {
+    CellId::new(name.into(), 0)
+}
+
+fn state(set: &History<indexmap::IndexSet<Cell>, Cell>) -> Vec<(String, usize)> {
+    set.iter().map(|c| (c.name.clone(), c.area)).collect()
+}
+
+#[test]
+fn undo_redo() {
+    let mut set = History::new(indexmap::IndexSet::new());
+    for name in ["a", "b", "c", "d"] {
+        set.insert(cell(name, 1));
+    }
+    set.checkpoint();
+    let s0 = state(&set);
+
+    set.get_mut(&id("b")).unwrap().area = 2;
+    set.get_mut(&id("b")).unwrap().area = 3;
+    assert_eq!(set.remove(&id("a")).unwrap().area, 1);
+    set.insert(cell("a", 4));
+    assert!(!set.insert(cell("a", 5)));
+    set.checkpoint();
+    let s1 = state(&set);
+    assert_eq!(s1, [("d".into(), 1), ("b".into(), 3), ("c".into(), 1), ("a".into(), 4)]);
+
+    assert_eq!(set.replace(cell("c", 6)).unwrap().area, 1);
+    set.iter_mut().for_each(|c| c.area *= 10);
+    set.remove(&id("d"));
+    let s2 = state(&set);
+
+    assert_eq!(set.undo_steps(), 3);
+    assert!(set.undo());
+    assert_eq!(state(&set), s1);
+    assert!(set.undo());
+    assert_eq!(state(&set), s0);
+    assert!(set.redo());
+    assert_eq!(state(&set), s1);
+    assert!(set.redo());
+    assert_eq!(state(&set), s2);
+    assert!(!set.redo());
+
+    // a new change discards the undone steps
+    set.undo();
+    set.get_mut(&id("c")).unwrap().area = 7;
+    assert_eq!(set.redo_steps(), 0);
+    assert!(!set.redo());
+    set.undo();
+    assert_eq!(state(&set), s1);
+}
+
+#[test]
+fn limit() {
+    let mut set = History::with_limit(indexmap::IndexSet::new(), 2);
+    for area in 0..5 {
+        set.insert(cell(&area.to_string(), area));
+        set.checkpoint();
+    }
+    assert_eq!(set.undo_steps(), 2);
+    assert!(set.undo() && set.undo());
+    assert!(!set.undo());
+    assert_eq!(set.len(), 3);
+    set.clear_history();
+    assert_eq!(set.redo_steps(), 0);
+    assert_eq!(set.into_inner().len(), 3);
+}
+
+#[test]
+fn hashset() {
+    let mut set = History::new(std::collections::HashSet::new());
+    set.insert(cell("a", 1));
+    set.insert(cell("b", 1));
+    set.checkpoint();
+    set.remove(&id("a"));
+    set.get_mut(&id("b")).unwrap().area = 2;
+    set.undo();
+    assert_eq!(set.len(), 2);
+    assert!(set.iter().all(|c| c.area == 1));
+    set.undo();
+    assert!(set.is_empty());
+}
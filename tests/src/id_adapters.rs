@@ -0,0 +1,92 @@
+// cargo expand --manifest-path ./tests/Cargo.toml id_adapters
+use core::hash::{BuildHasher, Hash};
+use mut_set::id_adapters::{self, *};
+use std::{hash::RandomState, path::PathBuf};
+
+/// `a == b` iff `a.cmp(b)` is `Equal`, and `a == b` implies equal hashes
+fn check_consistency<K: Hash + Ord + core::fmt::Debug>(keys: &[K]) {
+    let state = RandomState::new();
+    for a in keys {
+        for b in keys {
+            assert_eq!(a == b, a.cmp(b).is_eq(), "{a:?} {b:?}");
+            assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{a:?} {b:?}");
+            if a == b {
+                assert_eq!(state.hash_one(a), state.hash_one(b), "{a:?} {b:?}");
+            }
+        }
+    }
+}
+
+const F64S: [f64; 9] =
+    [0.0, -0.0, 1.0, 1.0004, 1.0006, -2.5, f64::NAN, f64::INFINITY, f64::NEG_INFINITY];
+const STRS: [&str; 9] =
+    ["foo", "FOO", "Foo ", " foo", "bar", "", "Straße", "STRASSE", "ÄÖ"];
+
+#[test]
+fn floats() {
+    check_consistency(&F64S.map(|f| f64_total(&f)));
+    check_consistency(&F64S.map(|f| f32_total(&(f as f32))));
+    check_consistency(&F64S.map(|f| f64_quantized::<1000>(&f)));
+    check_consistency(&F64S.map(|f| f32_quantized::<10>(&(f as f32))));
+    assert_eq!(f64_total(&f64::NAN), f64_total(&f64::NAN));
+    assert!(f64_total(&-0.0) < f64_total(&0.0));
+    assert_eq!(f64_quantized::<1000>(&1.0004), f64_quantized::<1000>(&1.0));
+    assert_ne!(f64_quantized::<1000>(&1.0006), f64_quantized::<1000>(&1.0));
+}
+
+#[test]
+fn strings() {
+    check_consistency(&STRS.map(ascii_case_insensitive));
+    check_consistency(&STRS.map(unicode_case_insensitive));
+    check_consistency(&STRS.map(trimmed));
+    assert_eq!(ascii_case_insensitive("foo"), ascii_case_insensitive("FOO"));
+    assert_ne!(ascii_case_insensitive("ä"), ascii_case_insensitive("Ä"));
+    assert_eq!(unicode_case_insensitive("ä"), unicode_case_insensitive("Ä"));
+    assert_eq!(trimmed(" foo "), trimmed("foo"));
+}
+
+#[test]
+fn paths() {
+    let paths =
+        ["a/b", "a/./b", "a/c/../b", "./a/b/", "../a", "/..", "/", "a/../..", ".."]
+            .map(PathBuf::from);
+    check_consistency(&paths.each_ref().map(|p| normalized_path(p)));
+    assert_eq!(normalized_path("a/c/../b/.".as_ref()), PathBuf::from("a/b"));
+    assert_eq!(normalized_path("a/../..".as_ref()), PathBuf::from(".."));
+    assert_eq!(normalized_path("/../a".as_ref()), PathBuf::from("/a"));
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id(into_hash_ord_fn = id_adapters::unicode_case_insensitive)]
+    pub name: String,
+    #[id(into_hash_ord_fn = id_adapters::f64_quantized::<100>)]
+    pub width: f64,
+    #[id(into_hash_ord_fn = id_adapters::normalized_path)]
+    pub path: PathBuf,
+    pub ctx: usize,
+}
+
+#[test]
+fn item() {
+    use mut_set::MutSetExt;
+
+    let mut set = indexmap::IndexSet::new();
+    set.insert(MyItem {
+        name: "Ä".into(),
+        width: 1.0,
+        path: "a/b".into(),
+        ctx: 0,
+    });
+    assert!(!set.insert(MyItem {
+        name: "ä".into(),
+        width: 1.001,
+        path: "a/./b".into(),
+        ctx: 1
+    }));
+    set.get_mut(&MyItemId::new("ä".into(), 0.999, "a/c/../b".into()))
+        .unwrap()
+        .ctx = 2;
+    assert_eq!(set[0].ctx, 2);
+}
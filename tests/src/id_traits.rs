@@ -0,0 +1,48 @@
+// cargo expand --manifest-path ./tests/Cargo.toml id_traits
+use mut_set::Item;
+
+#[inline]
+const fn f64_into_hash_ord_fn(val: &f64) -> ordered_float::OrderedFloat<f64> {
+    ordered_float::OrderedFloat(*val)
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id]
+    pub id1: usize,
+    pub ctx: bool,
+    #[id(into_hash_ord_fn = f64_into_hash_ord_fn)]
+    pub id2: f64,
+    #[id]
+    pub id3: Option<String>,
+}
+
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) struct NotClone;
+
+#[mut_set::derive::item]
+pub(super) struct NotCloneItem {
+    #[id]
+    pub id1: usize,
+    #[id]
+    pub id2: NotClone,
+}
+
+#[test]
+fn test() {
+    let item = MyItem { id1: 2, id2: 4.2, ctx: true, id3: None };
+    let id = item.to_id();
+    assert_eq!(format!("{id}"), "(2, 4.2, None)");
+    assert_eq!(format!("{id:?}"), "MyItemId { id1: 2, id2: 4.2, id3: None }");
+    assert!(id == MyItemId::from((2, 4.2, None)));
+    assert!(id == MyItemId::from(&item));
+    let (id1, id2, id3) = id.clone().into();
+    assert_eq!((id1, id2, id3), (2, 4.2, None));
+
+    let mut ids = std::collections::HashSet::new();
+    ids.insert(id);
+    assert!(ids.contains(item.id()));
+
+    let _ = NotCloneItem { id1: 0, id2: NotClone };
+}
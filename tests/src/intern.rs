@@ -0,0 +1,60 @@
+// cargo expand --manifest-path ./tests/Cargo.toml intern
+use mut_set::{
+    MutSetExt, PrehashedIndexSet,
+    intern::{Str, Symbol},
+};
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item]
+pub(super) struct Cell {
+    #[id(intern, prehashed)]
+    pub name: Symbol,
+    pub area: f64,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Pin {
+    #[id(intern)]
+    pub cell: Symbol,
+    #[id(intern)]
+    pub name: Symbol,
+    pub cap: f64,
+}
+
+#[test]
+fn symbols() {
+    let a = Symbol::new("intern_a");
+    assert_eq!(a, Symbol::from("intern_a".to_string()));
+    assert_eq!(Symbol::get("intern_a"), Some(a));
+    assert_eq!(Symbol::get("intern_never"), None);
+    assert_eq!(a < Symbol::new("intern_0"), "intern_a" < "intern_0");
+    assert_eq!(format!("{a} {a:?}"), r#"intern_a "intern_a""#);
+    assert_eq!(a.len(), 8);
+    let json = serde_json::to_string(&a).unwrap();
+    assert_eq!(serde_json::from_str::<Symbol>(&json).unwrap(), a);
+}
+
+#[test]
+fn interned_ids() {
+    let mut cells: PrehashedIndexSet<Cell> = ["and2", "or2", "inv"]
+        .into_iter()
+        .map(|name| Cell { name: name.into(), area: 1.0 })
+        .collect();
+    let id: CellId = cells[1].name;
+    assert_eq!(id, "or2");
+    cells.get_mut(Str::new("inv")).unwrap().area = 2.0;
+    cells.get_mut(&Symbol::new("and2")).unwrap().area = 3.0;
+    assert!(cells.get_mut(Str::new("nand2_never_interned")).is_none());
+    let areas: Vec<_> = cells.iter().map(|cell| cell.area).collect();
+    assert_eq!(areas, [3.0, 1.0, 2.0]);
+
+    let mut pins = indexmap::IndexSet::new();
+    pins.insert(Pin { cell: "and2".into(), name: "a".into(), cap: 1.0 });
+    pins.insert(Pin { cell: "and2".into(), name: "b".into(), cap: 1.0 });
+    // a composite id of symbols is `Copy`
+    let id = PinId::new("and2".into(), "b".into());
+    let copy = id;
+    pins.get_mut(&id).unwrap().cap = 2.0;
+    assert_eq!(pins.get(&copy).unwrap().cap, 2.0);
+}
@@ -0,0 +1,77 @@
+// cargo expand --manifest-path ./tests/Cargo.toml item_args
+use serde::Serialize;
+
+#[derive(Debug)]
+#[mut_set::derive::item(
+    id = "RouteKey",
+    readonly = "RouteView",
+    id_vis = pub(crate),
+    derive_id(Default, Serialize)
+)]
+pub(super) struct HTTPRoute {
+    #[id]
+    pub method: String,
+    #[id]
+    pub path: String,
+    pub handler: usize,
+}
+
+/// Same module name as `HTTPRoute` once in snake case
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct HttpRoute {
+    #[id]
+    pub method: String,
+    #[id]
+    pub path: String,
+}
+
+/// `Foo` would generate `FooId` by default
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct FooId {
+    #[id]
+    pub id: usize,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item(id = "FooKey")]
+pub(super) struct Foo {
+    #[id]
+    pub id: usize,
+    #[id]
+    pub sub: usize,
+}
+
+fn set_handler(route: &mut RouteView, handler: usize) {
+    route.handler = handler;
+}
+
+#[test]
+fn test() {
+    use mut_set::MutSetExt;
+
+    let mut set = indexmap::IndexSet::new();
+    set.insert(HTTPRoute { method: "GET".into(), path: "/".into(), handler: 0 });
+    set.insert(HTTPRoute {
+        method: "POST".into(),
+        path: "/".into(),
+        handler: 1,
+    });
+    let key = RouteKey::new("GET".into(), "/".into());
+    set_handler(set.get_mut(&key).unwrap(), 2);
+    assert_eq!(set.get(&key).unwrap().handler, 2);
+    assert_eq!(format!("{:?}", key.clone()), r#"RouteKey { method: "GET", path: "/" }"#);
+    assert_eq!(serde_json::to_string(&key).unwrap(), r#"{"method":"GET","path":"/"}"#);
+
+    let mut foo_ids = indexmap::IndexSet::new();
+    foo_ids.insert(FooId { id: 1 });
+    let mut foos = indexmap::IndexSet::new();
+    foos.insert(Foo { id: 1, sub: 2 });
+    assert!(foo_ids.contains(&1));
+    assert!(foos.contains(&FooKey::new(1, 2)));
+
+    let mut routes = indexmap::IndexSet::new();
+    routes.insert(HttpRoute { method: "GET".into(), path: "/".into() });
+    assert!(routes.contains(&HttpRouteId::new("GET".into(), "/".into())));
+}
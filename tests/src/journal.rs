@@ -0,0 +1,161 @@
+// cargo expand --manifest-path ./tests/Cargo.toml journal
+use indexmap::IndexSet;
+use mut_set::journal::Journaled;
+use serde::{Deserialize, Serialize};
+use std::{fs, io::Write, path::PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id]
+    pub id1: usize,
+    #[id]
+    pub id2: String,
+    pub ctx: usize,
+}
+
+type Set = Journaled<IndexSet<MyItem>, MyItem>;
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("mut_set_{name}_{}", std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        Self(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn item(id1: usize, ctx: usize) -> MyItem {
+    MyItem { id1, id2: id1.to_string(), ctx }
+}
+
+fn id(id1: usize) -> MyItemId {
+    MyItemId::new(id1, id1.to_string())
+}
+
+fn state(set: &IndexSet<MyItem>) -> Vec<(usize, usize)> {
+    set.iter().map(|item| (item.id1, item.ctx)).collect()
+}
+
+fn fill(set: &mut Set) {
+    for id1 in 0..5 {
+        assert!(set.insert(item(id1, 0)).unwrap());
+    }
+    assert!(!set.insert(item(1, 9)).unwrap());
+    assert_eq!(set.replace(item(2, 2)).unwrap().unwrap().ctx, 0);
+    assert_eq!(set.remove(&id(0)).unwrap().unwrap().ctx, 0);
+    assert!(set.remove(&id(0)).unwrap().is_none());
+    assert_eq!(set.modify(&id(3), |item| item.ctx = 3).unwrap(), Some(()));
+    assert_eq!(set.modify(&id(0), |item| item.ctx = 3).unwrap(), None);
+}
+
+#[test]
+fn reopen() {
+    let dir = TempDir::new("reopen");
+    let mut set = Set::open(&dir.0).unwrap();
+    fill(&mut set);
+    let expected = state(&set);
+    assert_eq!(expected, [(4, 0), (1, 0), (2, 2), (3, 3)]);
+    assert_eq!(set.journal_len(), 8);
+    set.sync().unwrap();
+    drop(set);
+    let set = Set::open(&dir.0).unwrap();
+    assert_eq!(state(&set), expected);
+    assert_eq!(set.torn_bytes(), 0);
+
+    let set: Journaled<std::collections::HashSet<MyItem>, _> =
+        Journaled::open(&dir.0).unwrap();
+    assert_eq!(set.len(), 4);
+}
+
+#[test]
+fn torn_write() {
+    let dir = TempDir::new("torn_write");
+    let mut set = Set::open(&dir.0).unwrap();
+    fill(&mut set);
+    let expected = state(&set);
+    drop(set);
+    let journal = dir.0.join("journal.jsonl");
+    let mut file = fs::OpenOptions::new().append(true).open(&journal).unwrap();
+    file.write_all(b"0123456789abcdef {\"put\":{\"id1\":7").unwrap();
+    drop(file);
+
+    let mut set = Set::open(&dir.0).unwrap();
+    assert_eq!(set.torn_bytes(), 32);
+    assert_eq!(state(&set), expected);
+    set.insert(item(7, 7)).unwrap();
+    drop(set);
+    let set = Set::open(&dir.0).unwrap();
+    assert_eq!(set.torn_bytes(), 0);
+    assert_eq!(state(&set).last(), Some(&(7, 7)));
+}
+
+#[test]
+fn corrupted() {
+    let dir = TempDir::new("corrupted");
+    let mut set = Set::open(&dir.0).unwrap();
+    fill(&mut set);
+    drop(set);
+    let journal = dir.0.join("journal.jsonl");
+    let content =
+        fs::read_to_string(&journal)
+            .unwrap()
+            .replacen("\"ctx\":0", "\"ctx\":1", 1);
+    fs::write(&journal, content).unwrap();
+    let err = Set::open(&dir.0).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().ends_with("journal.jsonl: line 2 is corrupted"));
+}
+
+#[test]
+fn missing_generation() {
+    let dir = TempDir::new("missing_generation");
+    let mut set = Set::open(&dir.0).unwrap();
+    fill(&mut set);
+    drop(set);
+    let journal = dir.0.join("journal.jsonl");
+    let content = fs::read_to_string(&journal).unwrap();
+    let records = content.split_once('\n').unwrap().1;
+    fs::write(&journal, records).unwrap();
+    // the records are kept, not truncated as a new journal
+    let err = Set::open(&dir.0).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().ends_with("journal.jsonl: line 1 is corrupted"));
+    assert_eq!(fs::read_to_string(&journal).unwrap(), records);
+
+    // an empty journal is a new one
+    fs::write(&journal, "").unwrap();
+    assert!(Set::open(&dir.0).unwrap().is_empty());
+}
+
+#[test]
+fn compact() {
+    let dir = TempDir::new("compact");
+    let mut set = Set::open(&dir.0).unwrap();
+    fill(&mut set);
+    let journal = dir.0.join("journal.jsonl");
+    let old_journal = fs::read(&journal).unwrap();
+    set.compact().unwrap();
+    assert_eq!(set.journal_len(), 0);
+    set.modify(&id(4), |item| item.ctx = 4).unwrap();
+    let expected = state(&set);
+    drop(set);
+    let set = Set::open(&dir.0).unwrap();
+    assert_eq!(state(&set), expected);
+    assert_eq!(set.journal_len(), 1);
+    drop(set);
+
+    // interrupted after the snapshot was replaced, the old journal is discarded
+    fs::write(&journal, old_journal).unwrap();
+    let set = Set::open(&dir.0).unwrap();
+    assert_eq!(set.journal_len(), 0);
+    assert_eq!(state(&set), [(4, 0), (1, 0), (2, 2), (3, 3)]);
+}
@@ -1,4 +1,32 @@
+#![cfg(test)]
 #![deny(clippy::non_canonical_partial_ord_impl)]
 mod basic;
+// the hand-written expansion allows `clippy::all`, which fulfills none of its
+// `#[expect(clippy::..)]`
+#[allow(unfulfilled_lint_expectations)]
 mod basic_expand;
+mod cache_hash;
+mod compare_with;
+mod compute_id;
+mod flatten;
+mod generics;
+mod handle;
+mod history;
+mod id_adapters;
+mod id_traits;
+mod intern;
+mod item_args;
+mod journal;
+mod observe;
+mod par;
+mod persistent;
+mod pointers;
+mod prehashed;
+mod small;
+mod sorted_vec;
+mod sync;
+mod tracked;
+mod transaction;
+mod ui;
 mod unique_id;
+mod view;
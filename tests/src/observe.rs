@@ -0,0 +1,98 @@
+// cargo expand --manifest-path ./tests/Cargo.toml observe
+use mut_set::observe::{Event, Observed, OwnedEvent};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id]
+    pub id: usize,
+    pub ctx: usize,
+}
+
+fn describe(event: &Event<'_, MyItem>) -> String {
+    match event {
+        Event::Inserted(item) => format!("+{}:{}", item.id, item.ctx),
+        Event::Removed(item) => format!("-{}:{}", item.id, item.ctx),
+        Event::Replaced { old, new } => format!("{}:{}->{}", new.id, old.ctx, new.ctx),
+        Event::Modified(id) => format!("~{id}"),
+    }
+}
+
+#[test]
+fn callbacks() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut set = Observed::new(indexmap::IndexSet::new());
+    let observer = set.observe({
+        let log = log.clone();
+        move |event| log.lock().unwrap().push(describe(event))
+    });
+    assert!(set.insert(MyItem { id: 1, ctx: 0 }));
+    assert!(!set.insert(MyItem { id: 1, ctx: 5 }));
+    set.insert(MyItem { id: 2, ctx: 0 });
+    assert_eq!(set.replace(MyItem { id: 1, ctx: 1 }).unwrap().ctx, 0);
+    assert!(set.replace(MyItem { id: 3, ctx: 3 }).is_none());
+    set.get_mut(&2).unwrap().ctx = 2;
+    assert_eq!(set.modify(&3, |item| std::mem::take(&mut item.ctx)), Some(3));
+    assert_eq!(set.modify(&4, |item| item.ctx), None);
+    assert_eq!(set.remove(&2).unwrap().ctx, 2);
+    assert!(set.remove(&2).is_none());
+    set.for_each_mut(|item| item.ctx += 10);
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["+1:0", "+2:0", "1:0->1", "+3:3", "~2", "~3", "-2:2", "~1", "~3"]
+    );
+    assert!(set.unobserve(observer));
+    assert!(!set.unobserve(observer));
+    set.remove(&1);
+    assert_eq!(log.lock().unwrap().len(), 9);
+    assert_eq!(set.iter().map(|item| item.ctx).collect::<Vec<_>>(), [10]);
+}
+
+#[test]
+fn channel() {
+    let mut set = Observed::new(std::collections::HashSet::new());
+    let events: std::sync::mpsc::Receiver<OwnedEvent<MyItem>> = set.subscribe();
+    let worker = std::thread::spawn(move || {
+        events
+            .into_iter()
+            .map(|event| match event {
+                OwnedEvent::Inserted(item) => item.id,
+                OwnedEvent::Removed(item) => item.id + 100,
+                OwnedEvent::Replaced { new, .. } => new.id + 200,
+                OwnedEvent::Modified(id) => id + 300,
+            })
+            .collect::<Vec<_>>()
+    });
+    set.insert(MyItem { id: 1, ctx: 0 });
+    set.replace(MyItem { id: 1, ctx: 1 });
+    set.get_mut(&1).unwrap().ctx += 1;
+    set.remove(&1);
+    // dropping the set closes the channel
+    drop(set);
+    assert_eq!(worker.join().unwrap(), [1, 201, 301, 101]);
+}
+
+#[test]
+fn dropped_receiver() {
+    let mut set = Observed::new(indexmap::IndexSet::new());
+    drop(set.subscribe());
+    // the closed channel is unregistered instead of failing
+    assert!(set.insert(MyItem { id: 1, ctx: 0 }));
+    set.get_mut(&1).unwrap().ctx = 1;
+    assert_eq!(set.into_inner()[0].ctx, 1);
+}
+
+#[test]
+fn local_observer() {
+    // observers stay on the thread of the set, they need not be `Send`
+    let count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut set = Observed::new(indexmap::IndexSet::new());
+    set.observe({
+        let count = count.clone();
+        move |_: &Event<'_, MyItem>| count.set(count.get() + 1)
+    });
+    set.insert(MyItem { id: 1, ctx: 0 });
+    set.replace(MyItem { id: 1, ctx: 1 });
+    assert_eq!(count.get(), 2);
+}
@@ -0,0 +1,50 @@
+// cargo expand --manifest-path ./tests/Cargo.toml par
+use mut_set::{ParIndexSetExt, ParMutSetExt};
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id]
+    pub id: usize,
+    pub ctx: usize,
+}
+
+/// Generic over the set, `par_iter_mut` is a `ParallelIterator`
+fn double<S: ParMutSetExt<MyItem>>(set: &mut S) {
+    set.par_iter_mut().for_each(|item| item.ctx = item.id * 2);
+}
+
+#[test]
+fn generic() {
+    let mut set: indexmap::IndexSet<_> =
+        (0..100).map(|id| MyItem { id, ctx: 0 }).collect();
+    double(&mut set);
+    assert!(set.iter().all(|item| item.ctx == item.id * 2));
+}
+
+#[test]
+fn hashset() {
+    let mut set: std::collections::HashSet<_> =
+        (0..1000).map(|id| MyItem { id, ctx: 0 }).collect();
+    set.par_iter_mut().for_each(|item| item.ctx = item.id * 2);
+    assert!(set.iter().all(|item| item.ctx == item.id * 2));
+}
+
+#[test]
+fn indexset() {
+    let mut set: indexmap::IndexSet<_> =
+        (0..1000).map(|id| MyItem { id, ctx: 0 }).collect();
+    assert_eq!(set.par_iter_mut().len(), 1000);
+    set.par_iter_mut().enumerate().for_each(|(i, item)| item.ctx = i);
+    assert!(set.iter().all(|item| item.ctx == item.id));
+    set.par_for_each_mut(|item| item.ctx += 1);
+    set.par_retain_mut(|item| {
+        item.ctx *= 10;
+        item.id % 3 == 0
+    });
+    assert_eq!(set.len(), 334);
+    assert!(set.iter().enumerate().all(|(i, item)| item.id == i * 3));
+    assert!(set.iter().all(|item| item.ctx == (item.id + 1) * 10));
+    assert!(set.get(&3).is_some());
+}
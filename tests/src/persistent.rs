@@ -0,0 +1,103 @@
+// cargo expand --manifest-path ./tests/Cargo.toml persistent
+use mut_set::{MutSetExt, persistent::PersistentSet};
+use std::hash::{BuildHasherDefault, Hasher};
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id]
+    pub id: usize,
+    pub ctx: usize,
+}
+
+/// Only 3 distinct hashes, to exercise the collision nodes
+#[derive(Default)]
+struct BadHasher(u64);
+
+impl Hasher for BadHasher {
+    fn finish(&self) -> u64 {
+        self.0 % 3
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        self.0 += bytes.iter().map(|&b| u64::from(b)).sum::<u64>();
+    }
+}
+
+fn sorted<'a>(set: impl IntoIterator<Item = &'a MyItem>) -> Vec<(usize, usize)> {
+    let mut items: Vec<_> = set.into_iter().map(|item| (item.id, item.ctx)).collect();
+    items.sort_unstable();
+    items
+}
+
+#[test]
+fn copy_on_write() {
+    let mut set: PersistentSet<_> = (0..1000).map(|id| MyItem { id, ctx: 0 }).collect();
+    assert_eq!(set.len(), 1000);
+    assert!(!set.insert(MyItem { id: 1, ctx: 1 }));
+    let snapshot = set.clone();
+    assert!(set.ptr_eq(&snapshot));
+    set.get_mut(&1).unwrap().ctx = 1;
+    assert!(set.get_mut(&1000).is_none());
+    assert!(!set.ptr_eq(&snapshot));
+    assert_eq!(set.get(&1).unwrap().ctx, 1);
+    assert_eq!(snapshot.get(&1).unwrap().ctx, 0);
+
+    let reader =
+        std::thread::spawn(move || snapshot.iter().map(|item| item.ctx).sum::<usize>());
+    set.iter_mut().for_each(|item| item.ctx = item.id);
+    for id in (0..1000).step_by(2) {
+        assert_eq!(set.remove(&id).unwrap().id, id);
+    }
+    assert!(set.remove(&0).is_none());
+    assert_eq!(reader.join().unwrap(), 0);
+    assert_eq!(set.len(), 500);
+    assert_eq!(set.iter().len(), 500);
+    assert_eq!(
+        sorted(&set),
+        (0..1000)
+            .filter(|id| id % 2 == 1)
+            .map(|id| (id, id))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn collisions() {
+    let mut set: PersistentSet<_, BuildHasherDefault<BadHasher>> =
+        PersistentSet::default();
+    for id in 0..100 {
+        assert!(set.replace(MyItem { id, ctx: 0 }).is_none());
+    }
+    assert_eq!(set.replace(MyItem { id: 7, ctx: 7 }).unwrap().ctx, 0);
+    let snapshot = set.clone();
+    for id in (0..100).filter(|id| id % 3 != 0) {
+        assert!(set.remove(&id).is_some());
+    }
+    set.get_mut(&9).unwrap().ctx = 9;
+    assert_eq!(
+        sorted(&set),
+        (0..100)
+            .step_by(3)
+            .map(|id| (id, if id == 9 { 9 } else { 0 }))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(snapshot.len(), 100);
+    assert_eq!(snapshot.get(&7).unwrap().ctx, 7);
+    assert!(snapshot.iter().all(|item| item.ctx == 0 || item.id == 7));
+    for id in (0..100).step_by(3) {
+        set.remove(&id);
+    }
+    assert!(set.is_empty() && set.iter().next().is_none());
+}
+
+#[test]
+fn par_iter_mut() {
+    use mut_set::ParMutSetExt;
+    use rayon::iter::ParallelIterator;
+    let mut set: PersistentSet<_> = (0..1000).map(|id| MyItem { id, ctx: 0 }).collect();
+    let snapshot = set.clone();
+    set.par_iter_mut().for_each(|item| item.ctx = item.id * 2);
+    assert!(set.iter().all(|item| item.ctx == item.id * 2));
+    assert!(snapshot.iter().all(|item| item.ctx == 0));
+    assert_eq!(format!("{:?}", PersistentSet::<MyItem>::new()), "{}");
+}
@@ -0,0 +1,118 @@
+// cargo expand --manifest-path ./tests/Cargo.toml pointers
+use indexmap::IndexSet;
+use mut_set::{Item, MutSetExt, tracked::Tracked};
+use std::{rc::Rc, sync::Arc};
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item]
+pub(super) struct Net {
+    #[id]
+    pub name: String,
+    pub cap: f64,
+}
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item]
+pub(super) struct Pin {
+    #[id]
+    pub cell: String,
+    #[id]
+    pub name: String,
+    pub cap: f64,
+}
+
+fn pin(cell: &str, name: &str) -> Pin {
+    Pin { cell: cell.into(), name: name.into(), cap: 1.0 }
+}
+
+#[test]
+fn boxed() {
+    let mut nets: IndexSet<Box<Net>> = ["a", "b"]
+        .into_iter()
+        .map(|name| Box::new(Net { name: name.into(), cap: 1.0 }))
+        .collect();
+    nets.get_mut(&"b".to_string()).unwrap().cap = 2.0;
+    assert_eq!(nets.get(&"b".to_string()).unwrap().cap, 2.0);
+    assert_eq!(nets[1].id(), "b");
+    for net in nets.iter_mut() {
+        net.cap += 1.0;
+    }
+    assert_eq!(nets.iter().map(|net| net.cap).collect::<Vec<_>>(), [2.0, 3.0]);
+}
+
+#[test]
+fn copy_on_write() {
+    let shared = Arc::new(pin("and2", "y"));
+    let mut pins: IndexSet<Arc<Pin>> = IndexSet::new();
+    pins.insert(shared.clone());
+    pins.insert(Arc::new(pin("and2", "a")));
+    let id = PinId::new("and2".into(), "y".into());
+    pins.get_mut(&id).unwrap().cap = 2.0;
+    assert_eq!(pins.get(&id).unwrap().cap, 2.0);
+    assert_eq!(shared.cap, 1.0);
+    assert!(!Arc::ptr_eq(&pins[0], &shared));
+    // owned only by the set, not cloned again
+    let unique = Arc::as_ptr(&pins[1]);
+    for pin in pins.iter_mut() {
+        pin.cap *= 10.0;
+    }
+    assert_eq!(Arc::as_ptr(&pins[1]), unique);
+    assert_eq!(pins.iter().map(|pin| pin.cap).collect::<Vec<_>>(), [20.0, 10.0]);
+    assert_eq!(shared.cap, 1.0);
+}
+
+#[test]
+fn rc_iter_mut() {
+    let shared: Vec<_> =
+        ["a", "b"].into_iter().map(|name| Rc::new(pin("or2", name))).collect();
+    let mut pins: IndexSet<Rc<Pin>> = shared.iter().cloned().collect();
+    for pin in pins.iter_mut() {
+        pin.cap = 0.0;
+    }
+    assert!(pins.iter().all(|pin| pin.cap == 0.0));
+    assert!(shared.iter().all(|pin| pin.cap == 1.0 && Rc::strong_count(pin) == 1));
+    assert!(pins.contains(&PinId::new("or2".into(), "b".into())));
+}
+
+#[test]
+fn tracked() {
+    let nets: IndexSet<Box<Net>> = ["a", "b"]
+        .into_iter()
+        .map(|name| Box::new(Net { name: name.into(), cap: 1.0 }))
+        .collect();
+    let mut nets = Tracked::<_, Net>::new(nets);
+    nets.get_mut(&"b".to_string()).unwrap().cap = 2.0;
+    assert!(nets.is_dirty(&"b".to_string()));
+    assert!(!nets.is_dirty(&"a".to_string()));
+
+    let shared = Arc::new(pin("and2", "y"));
+    let mut pins = Tracked::<_, Pin>::new(IndexSet::<Arc<Pin>>::from([shared.clone()]));
+    for pin in pins.iter_mut() {
+        pin.cap = 2.0;
+    }
+    assert_eq!(pins.dirty_ids().len(), 1);
+    assert_eq!((pins[0].cap, shared.cap), (2.0, 1.0));
+}
+
+#[test]
+fn par_iter_mut() {
+    use mut_set::ParMutSetExt;
+    use rayon::iter::ParallelIterator;
+    let mut nets: IndexSet<Box<Net>> = (0..100)
+        .map(|i| Box::new(Net { name: i.to_string(), cap: 1.0 }))
+        .collect();
+    nets.par_iter_mut().for_each(|net| net.cap = 2.0);
+    assert!(nets.iter().all(|net| net.cap == 2.0));
+
+    let shared = Arc::new(pin("and2", "y"));
+    let mut pins: IndexSet<Arc<Pin>> =
+        IndexSet::from([shared.clone(), Arc::new(pin("and2", "a"))]);
+    pins.par_iter_mut().for_each(|pin| pin.cap = 2.0);
+    assert!(pins.iter().all(|pin| pin.cap == 2.0));
+    assert_eq!(shared.cap, 1.0);
+
+    let shared = Rc::new(pin("or2", "y"));
+    let mut pins: IndexSet<Rc<Pin>> = IndexSet::from([shared.clone()]);
+    pins.par_iter_mut().for_each(|pin| pin.cap = 2.0);
+    assert_eq!((pins[0].cap, shared.cap), (2.0, 1.0));
+}
@@ -0,0 +1,100 @@
+// cargo expand --manifest-path ./tests/Cargo.toml prehashed
+use mut_set::{MutSetExt, NoHashBuildHasher, PrehashedHashSet, PrehashedIndexSet};
+use std::hash::{BuildHasher, Hasher};
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Node {
+    #[id(prehashed)]
+    pub id: u32,
+    pub ctx: usize,
+}
+
+#[test]
+fn prehashed_set() {
+    let mut set: PrehashedIndexSet<Node> =
+        (0..100).map(|id| Node { id, ctx: 0 }).collect();
+    set.get_mut(&7).unwrap().ctx = 1;
+    assert_eq!(set.get(&7).unwrap().ctx, 1);
+    assert_eq!(set.hasher().hash_one(Node { id: 7, ctx: 0 }), 7);
+
+    let mut set = PrehashedHashSet::default();
+    set.insert(Node { id: u32::MAX, ctx: 0 });
+    assert!(set.contains(&u32::MAX));
+}
+
+#[test]
+fn integers() {
+    assert_eq!(NoHashBuildHasher.hash_one(3u8), 3);
+    assert_eq!(NoHashBuildHasher.hash_one(3u16), 3);
+    assert_eq!(NoHashBuildHasher.hash_one(3u32), 3);
+    assert_eq!(NoHashBuildHasher.hash_one(3usize), 3);
+    assert_eq!(NoHashBuildHasher.hash_one(-1i32), u64::from(u32::MAX));
+    assert_eq!(NoHashBuildHasher.hash_one(-1i64), u64::MAX);
+    assert_eq!(NoHashBuildHasher.hash_one(1u128 << 64 | 3), 2);
+    let mut hasher = NoHashBuildHasher.build_hasher();
+    hasher.write(&[1, 2]);
+    assert_eq!(hasher.finish(), 0x0201);
+}
+
+#[test]
+fn multiple_writes() {
+    assert_ne!(NoHashBuildHasher.hash_one((1u64, 2u64)), 2);
+    assert_ne!(
+        NoHashBuildHasher.hash_one((1u64, 2u64)),
+        NoHashBuildHasher.hash_one((2u64, 1u64))
+    );
+    assert_ne!(
+        NoHashBuildHasher.hash_one(Some(0u64)),
+        NoHashBuildHasher.hash_one(None::<u64>)
+    );
+    assert_ne!(
+        NoHashBuildHasher.hash_one(Some(1u64)),
+        NoHashBuildHasher.hash_one(Some(2u64))
+    );
+    // the same in debug and release builds
+    let mut hasher = NoHashBuildHasher.build_hasher();
+    hasher.write_u64(1);
+    hasher.write_u64(2);
+    let expected = (1u64.rotate_left(5) ^ 2).wrapping_mul(0x517c_c1b7_2722_0a95);
+    assert_eq!(hasher.finish(), expected);
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Optional {
+    #[id]
+    pub id: Option<u64>,
+    pub ctx: usize,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Pair {
+    #[id]
+    pub a: u64,
+    #[id]
+    pub b: u64,
+    pub ctx: usize,
+}
+
+#[test]
+fn multiple_writes_ids() {
+    let mut set: indexmap::IndexSet<Optional, NoHashBuildHasher> =
+        [None, Some(0), Some(1)]
+            .into_iter()
+            .map(|id| Optional { id, ctx: 0 })
+            .collect();
+    assert_eq!(set.len(), 3);
+    set.get_mut(&None).unwrap().ctx = 1;
+    set.get_mut(&Some(0)).unwrap().ctx = 2;
+    assert_eq!(set.iter().map(|item| item.ctx).collect::<Vec<_>>(), [1, 2, 0]);
+
+    let mut set: indexmap::IndexSet<Pair, NoHashBuildHasher> =
+        (0..10).map(|a| Pair { a, b: 9 - a, ctx: 0 }).collect();
+    assert_eq!(set.len(), 10);
+    set.get_mut(&PairId::new(2, 7)).unwrap().ctx = 1;
+    assert_eq!(set.get(&PairId::new(2, 7)).unwrap().ctx, 1);
+    assert_eq!(set.get(&PairId::new(7, 2)).unwrap().ctx, 0);
+    assert!(set.get(&PairId::new(2, 2)).is_none());
+}
@@ -0,0 +1,169 @@
+// cargo expand --manifest-path ./tests/Cargo.toml small
+use mut_set::{MutSetExt, ParMutSetExt, small::SmallMutSet};
+use rayon::iter::ParallelIterator;
+use std::rc::Rc;
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id]
+    pub id: usize,
+    pub ctx: usize,
+}
+
+fn items<const N: usize>(set: &SmallMutSet<MyItem, N>) -> Vec<(usize, usize)> {
+    set.iter().map(|item| (item.id, item.ctx)).collect()
+}
+
+#[test]
+fn spill() {
+    let mut set = SmallMutSet::<MyItem, 3>::new();
+    for id in [2, 0, 1] {
+        assert!(set.insert(MyItem { id, ctx: 0 }));
+    }
+    assert!(!set.insert(MyItem { id: 0, ctx: 1 }));
+    assert_eq!(set.replace(MyItem { id: 0, ctx: 2 }).unwrap().ctx, 0);
+    set.get_mut(&1).unwrap().ctx = 1;
+    assert!(!set.spilled());
+    assert_eq!(items(&set), [(2, 0), (0, 2), (1, 1)]);
+    assert_eq!(set.remove(&0).unwrap().ctx, 2);
+    assert!(set.remove(&0).is_none());
+    assert_eq!(items(&set), [(2, 0), (1, 1)]);
+
+    set.extend([3, 4].map(|id| MyItem { id, ctx: 0 }));
+    assert!(set.spilled());
+    assert_eq!(set.len(), 4);
+    for item in set.iter_mut() {
+        item.ctx += 1;
+    }
+    set.par_iter_mut().for_each(|item| item.ctx *= 10);
+    assert_eq!(set.remove(&1).unwrap().ctx, 20);
+    assert_eq!(items(&set), [(2, 10), (3, 10), (4, 10)]);
+    assert_eq!(
+        set.clone().into_iter().map(|item| item.id).collect::<Vec<_>>(),
+        [2, 3, 4]
+    );
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Counted {
+    #[id]
+    pub id: usize,
+    pub rc: Rc<()>,
+}
+
+#[test]
+fn drops() {
+    let rc = Rc::new(());
+    let counted = |id| Counted { id, rc: rc.clone() };
+    let mut set = SmallMutSet::<Counted, 4>::new();
+    set.extend((0..4).map(counted));
+    assert!(!set.insert(counted(1)));
+    assert_eq!(Rc::strong_count(&rc), 5);
+    drop(set.remove(&1));
+    assert_eq!(Rc::strong_count(&rc), 4);
+
+    let mut iter = set.into_iter();
+    assert_eq!(iter.next().unwrap().id, 0);
+    assert_eq!(iter.len(), 2);
+    drop(iter);
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    let mut set: SmallMutSet<_, 4> = (0..6).map(counted).collect();
+    assert!(set.spilled());
+    set.clear();
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
+
+// the inline storage is `unsafe`, run under Miri by
+// `cargo +nightly miri test -p tests --lib small`
+
+/// Panics when dropped with `panics` set
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Bomb {
+    #[id]
+    pub id: usize,
+    pub rc: Rc<()>,
+    pub panics: bool,
+}
+
+impl Drop for Bomb {
+    fn drop(&mut self) {
+        if self.panics && !std::thread::panicking() {
+            panic!("bomb {}", self.id);
+        }
+    }
+}
+
+fn bombs(rc: &Rc<()>, panics: usize) -> SmallMutSet<Bomb, 4> {
+    (0..4)
+        .map(|id| Bomb { id, rc: rc.clone(), panics: id == panics })
+        .collect()
+}
+
+fn panics(f: impl FnOnce()) -> bool {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).is_err()
+}
+
+#[test]
+fn panic_in_drop() {
+    let rc = Rc::new(());
+    // the items after the panicking one are still dropped, once
+    let set = bombs(&rc, 1);
+    assert!(!set.spilled());
+    assert!(panics(|| drop(set)));
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    let mut set = bombs(&rc, 2);
+    assert!(panics(|| set.clear()));
+    assert_eq!(Rc::strong_count(&rc), 1);
+    assert!(set.is_empty());
+    set.insert(Bomb { id: 0, rc: rc.clone(), panics: false });
+    drop(set);
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
+
+#[test]
+fn partially_consumed_into_iter() {
+    let rc = Rc::new(());
+    let mut iter = bombs(&rc, 4).into_iter();
+    assert_eq!(iter.next().unwrap().id, 0);
+    assert_eq!(iter.next().unwrap().id, 1);
+    assert_eq!(Rc::strong_count(&rc), 3);
+    drop(iter);
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    // consumed to the end, nothing is left to drop
+    let mut iter = bombs(&rc, 4).into_iter();
+    assert_eq!(iter.by_ref().count(), 4);
+    assert!(iter.next().is_none());
+    drop(iter);
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    // a panicking `Drop` among the rest does not leak the others
+    let mut iter = bombs(&rc, 2).into_iter();
+    assert_eq!(iter.next().unwrap().id, 0);
+    assert!(panics(|| drop(iter)));
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    // the items already yielded are owned by the caller
+    let mut iter = bombs(&rc, 0).into_iter();
+    let first = iter.next().unwrap();
+    drop(iter);
+    assert_eq!(Rc::strong_count(&rc), 2);
+    assert!(panics(|| drop(first)));
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
+
+#[test]
+fn spill_moves_each_item_once() {
+    let rc = Rc::new(());
+    let mut set = bombs(&rc, 4);
+    set.insert(Bomb { id: 4, rc: rc.clone(), panics: false });
+    assert!(set.spilled());
+    assert_eq!(Rc::strong_count(&rc), 6);
+    drop(set);
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
@@ -0,0 +1,102 @@
+// cargo expand --manifest-path ./tests/Cargo.toml sorted_vec
+use mut_set::sorted_vec::{ParSortedMutSetExt, SortedMutSetExt, SortedVecSet};
+use rayon::iter::ParallelIterator;
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id]
+    pub row: usize,
+    #[id]
+    pub col: usize,
+    pub ctx: usize,
+}
+
+fn item(row: usize, col: usize, ctx: usize) -> MyItem {
+    MyItem { row, col, ctx }
+}
+
+fn ids(set: &SortedVecSet<MyItem>) -> Vec<(usize, usize, usize)> {
+    set.iter().map(|item| (item.row, item.col, item.ctx)).collect()
+}
+
+#[test]
+fn sorted_vec() {
+    let mut set =
+        SortedVecSet::from_unsorted(vec![item(1, 0, 0), item(0, 1, 0), item(1, 0, 1)]);
+    assert_eq!(ids(&set), [(0, 1, 0), (1, 0, 0)]);
+    assert!(set.insert(item(0, 0, 0)));
+    assert!(!set.insert(item(0, 0, 1)));
+    assert_eq!(set.replace(item(2, 2, 2)).map(|item| item.ctx), None);
+    assert_eq!(set.replace(item(2, 2, 3)).map(|item| item.ctx), Some(2));
+    assert_eq!(ids(&set), [(0, 0, 0), (0, 1, 0), (1, 0, 0), (2, 2, 3)]);
+
+    set.get_mut(&MyItemId::new(0, 1)).unwrap().ctx = 5;
+    assert!(set.get_mut(&MyItemId::new(3, 0)).is_none());
+    assert!(set.contains(&MyItemId::new(0, 1)));
+    assert_eq!(set.get(&MyItemId::new(0, 1)).unwrap().ctx, 5);
+    for item in set.iter_mut() {
+        item.ctx += 1;
+    }
+    set.range_mut::<MyItemId, _>(..&MyItemId::new(0, 1))
+        .next()
+        .unwrap()
+        .ctx = 9;
+    set.par_iter_mut().for_each(|item| item.ctx *= 2);
+    assert_eq!(ids(&set), [(0, 0, 18), (0, 1, 12), (1, 0, 2), (2, 2, 8)]);
+    set.as_mut_slice()[3].ctx += 1;
+    assert_eq!(ids(&set), [(0, 0, 18), (0, 1, 12), (1, 0, 2), (2, 2, 9)]);
+
+    assert_eq!(set.remove(&MyItemId::new(1, 0)).unwrap().ctx, 2);
+    assert!(set.remove(&MyItemId::new(1, 0)).is_none());
+    set.extend([item(1, 1, 0), item(0, 0, 0), item(3, 0, 0)]);
+    assert_eq!(ids(&set), [(0, 0, 18), (0, 1, 12), (1, 1, 0), (2, 2, 9), (3, 0, 0)]);
+    assert_eq!(set.into_vec().len(), 5);
+}
+
+#[test]
+fn range() {
+    let mut set: SortedVecSet<_> = (0..10).rev().map(|row| item(row, 0, 0)).collect();
+    let (a, b) = (MyItemId::new(2, 0), MyItemId::new(5, 0));
+    let rows: Vec<_> = set.range::<MyItemId, _>(&a..&b).map(|item| item.row).collect();
+    assert_eq!(rows, [2, 3, 4]);
+    for item in set.range_mut::<MyItemId, _>(&a..=&b) {
+        item.ctx = 1;
+    }
+    for item in set.range_mut::<MyItemId, _>(..) {
+        item.ctx += 1;
+    }
+    assert_eq!(
+        set.iter().map(|item| item.ctx).collect::<Vec<_>>(),
+        [1, 1, 2, 2, 2, 2, 1, 1, 1, 1]
+    );
+    assert_eq!(set.range::<MyItemId, _>(&MyItemId::new(9, 1)..).len(), 0);
+    assert_eq!(set.range::<MyItemId, _>(..&MyItemId::new(0, 0)).len(), 0);
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct Boxed {
+    #[id]
+    pub key: u64,
+    pub value: u64,
+}
+
+#[test]
+fn boxed() {
+    let mut set: SortedVecSet<Box<Boxed>> =
+        (0..4).rev().map(|key| Box::new(Boxed { key, value: key })).collect();
+    for item in set.iter_mut() {
+        assert_eq!(item.key, item.value);
+        item.value += 10;
+    }
+    for item in set.range_mut::<u64, _>(1..3) {
+        item.value *= 2;
+    }
+    set.par_iter_mut().for_each(|item| item.value += 1);
+    set.get_mut(&3).unwrap().value = 0;
+    assert_eq!(
+        set.iter().map(|item| (item.key, item.value)).collect::<Vec<_>>(),
+        [(0, 11), (1, 23), (2, 25), (3, 0)]
+    );
+}
@@ -0,0 +1,71 @@
+// cargo expand --manifest-path ./tests/Cargo.toml sync
+use mut_set::sync::ShardedSet;
+
+#[derive(Debug)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id]
+    pub id: usize,
+    pub ctx: usize,
+}
+
+#[test]
+fn concurrent() {
+    let set = ShardedSet::with_shards(5);
+    assert_eq!(set.shard_count(), 8);
+    std::thread::scope(|s| {
+        for t in 0..4 {
+            let set = &set;
+            s.spawn(move || {
+                for id in (t..1000).step_by(4) {
+                    assert!(set.insert(MyItem { id, ctx: 0 }));
+                }
+                for id in (t..1000).step_by(4) {
+                    let mut item = set.get_mut(&id).unwrap();
+                    item.ctx = item.id * 2;
+                }
+            });
+        }
+    });
+    assert_eq!(set.len(), 1000);
+    assert!(!set.insert(MyItem { id: 1, ctx: 0 }));
+    assert_eq!(set.get(&1).unwrap().ctx, 2);
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            for id in (0..1000).filter(|id| id % 2 == 1) {
+                assert_eq!(set.remove(&id).unwrap().id, id);
+            }
+        });
+        s.spawn(|| {
+            set.iter_mut().for_each(|mut item| item.ctx += 1);
+        });
+    });
+    assert_eq!(set.len(), 500);
+    assert!(!set.contains(&1));
+    let set = set.into_inner();
+    assert!(set.iter().all(|item| item.id % 2 == 0 && item.ctx == item.id * 2 + 1));
+}
+
+#[test]
+fn iter_mut() {
+    let set = ShardedSet::new();
+    assert!(set.is_empty());
+    for id in 0..100 {
+        set.insert(MyItem { id, ctx: 0 });
+    }
+    for (n, mut item) in set.iter_mut().enumerate() {
+        item.ctx = n;
+    }
+    let mut ctx: Vec<_> = set.into_inner().into_iter().map(|item| item.ctx).collect();
+    ctx.sort_unstable();
+    assert_eq!(ctx, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn replace() {
+    let set = ShardedSet::new();
+    set.insert(MyItem { id: 1, ctx: 1 });
+    assert_eq!(set.replace(MyItem { id: 1, ctx: 2 }).unwrap().ctx, 1);
+    assert_eq!(set.get(&1).unwrap().ctx, 2);
+    assert_eq!(format!("{set:?}"), "{MyItem { id: 1, ctx: 2 }}");
+}
@@ -0,0 +1,68 @@
+// cargo expand --manifest-path ./tests/Cargo.toml tracked
+use mut_set::tracked::Tracked;
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id]
+    pub id1: usize,
+    #[id]
+    pub id2: String,
+    pub ctx: usize,
+}
+
+fn items() -> impl Iterator<Item = MyItem> {
+    (0..10).map(|id1| MyItem { id1, id2: id1.to_string(), ctx: 0 })
+}
+
+#[test]
+fn get_mut() {
+    let mut set = Tracked::new(items().collect::<indexmap::IndexSet<_>>());
+    assert!(set.dirty_ids().is_empty());
+    set.get_mut(&MyItemId::new(3, "3".into())).unwrap().ctx = 1;
+    set.get_mut(&MyItemId::new(1, "1".into())).unwrap().ctx = 1;
+    set.get_mut(&MyItemId::new(3, "3".into())).unwrap().ctx = 2;
+    assert!(set.get_mut(&MyItemId::new(3, "4".into())).is_none());
+    assert!(set.is_dirty(&MyItemId::new(3, "3".into())));
+    assert!(!set.is_dirty(&MyItemId::new(2, "2".into())));
+    assert_eq!(
+        set.take_dirty().into_iter().collect::<Vec<_>>(),
+        [MyItemId::new(3, "3".into()), MyItemId::new(1, "1".into())]
+    );
+    assert!(set.dirty_ids().is_empty());
+    // read access does not mark items
+    assert_eq!(set.iter().filter(|item| item.ctx != 0).count(), 2);
+    assert!(set.dirty_ids().is_empty());
+}
+
+#[test]
+fn iter_mut() {
+    let mut set = Tracked::new(items().collect::<std::collections::HashSet<_>>());
+    set.iter_mut()
+        .filter(|item| item.id1 % 2 == 0)
+        .for_each(|item| item.ctx += 1);
+    // every yielded item was mutably borrowed
+    assert_eq!(set.dirty_ids().len(), 10);
+    set.clear_dirty();
+    set.inner_mut().insert(MyItem { id1: 10, id2: "10".into(), ctx: 0 });
+    assert!(set.dirty_ids().is_empty());
+    let item = set.iter().find(|item| item.id1 == 10).unwrap().clone();
+    set.mark_dirty(&item);
+    assert!(set.is_dirty(&MyItemId::new(10, "10".into())));
+}
+
+#[test]
+fn update() {
+    let mut set: Tracked<indexmap::IndexSet<_>, _> = Tracked::default();
+    set.inner_mut().extend(items());
+    let id = MyItemId::new(4, "4".into());
+    assert_eq!(set.update(&id, |item| item.ctx, |item| item.ctx), Some(0));
+    assert!(!set.is_dirty(&id));
+    set.update(&id, |item| item.ctx, |item| item.ctx = 4);
+    assert!(set.is_dirty(&id));
+    set.update_all(|item| item.ctx, |item| item.ctx = item.id1 / 2 * 2);
+    let dirty: Vec<_> = set.dirty_ids().iter().map(|id| id.id1).collect();
+    assert_eq!(dirty, [4, 2, 3, 5, 6, 7, 8, 9]);
+    let set = set.into_inner();
+    assert!(set.iter().all(|item| item.ctx == item.id1 / 2 * 2));
+}
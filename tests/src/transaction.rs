@@ -0,0 +1,92 @@
+// cargo expand --manifest-path ./tests/Cargo.toml transaction
+use mut_set::Transactional;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Snapshot(usize, String, usize);
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item]
+pub(super) struct MyItem {
+    #[id]
+    pub id1: usize,
+    #[id]
+    pub id2: String,
+    pub ctx: usize,
+}
+
+fn snapshot<'a>(set: impl IntoIterator<Item = &'a MyItem>) -> Vec<Snapshot> {
+    set.into_iter()
+        .map(|item| Snapshot(item.id1, item.id2.clone(), item.ctx))
+        .collect()
+}
+
+fn items() -> indexmap::IndexSet<MyItem> {
+    (0..10)
+        .map(|id1| MyItem { id1, id2: id1.to_string(), ctx: id1 })
+        .collect()
+}
+
+fn id(id1: usize) -> MyItemId {
+    MyItemId::new(id1, id1.to_string())
+}
+
+fn mutate(
+    tx: &mut mut_set::transaction::Transaction<'_, MyItem, impl Transactional<MyItem>>,
+) {
+    tx.get_mut(&id(3)).unwrap().ctx = 30;
+    assert_eq!(tx.remove(&id(3)).unwrap().ctx, 30);
+    assert!(tx.remove(&id(3)).is_none());
+    assert!(tx.insert(MyItem { id1: 3, id2: "3".into(), ctx: 300 }));
+    assert!(!tx.insert(MyItem { id1: 3, id2: "3".into(), ctx: 0 }));
+    tx.remove(&id(0));
+    tx.remove(&id(5));
+    assert!(tx.replace(MyItem { id1: 11, id2: "11".into(), ctx: 0 }).is_none());
+    assert_eq!(tx.replace(MyItem { id1: 7, id2: "7".into(), ctx: 70 }).unwrap().ctx, 7);
+    tx.iter_mut().for_each(|item| item.ctx += 1);
+    tx.get_mut(&id(11)).unwrap().ctx = 110;
+    tx.remove(&id(11));
+}
+
+#[test]
+fn rollback_on_err() {
+    let mut set = items();
+    let before = snapshot(&set);
+    let result: Result<(), &str> = set.transaction(|tx| {
+        mutate(tx);
+        assert_eq!(tx.len(), 8);
+        Err("invalid")
+    });
+    assert_eq!(result, Err("invalid"));
+    // the order is restored too
+    assert_eq!(snapshot(&set), before);
+}
+
+#[test]
+fn rollback_on_panic() {
+    let mut set: std::collections::HashSet<_> = items().into_iter().collect();
+    let mut before = snapshot(&set);
+    before.sort_by_key(|s| s.0);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        set.transaction(|tx| -> Result<(), ()> {
+            mutate(tx);
+            panic!("validation")
+        })
+    }));
+    assert!(result.is_err());
+    let mut after = snapshot(&set);
+    after.sort_by_key(|s| s.0);
+    assert_eq!(after, before);
+}
+
+#[test]
+fn commit() {
+    let mut set = items();
+    let len = set.transaction(|tx| -> Result<_, ()> {
+        mutate(tx);
+        Ok(tx.len())
+    });
+    assert_eq!(len, Ok(8));
+    let ctx: Vec<_> = set.iter().map(|item| (item.id1, item.ctx)).collect();
+    assert_eq!(ctx, [(3, 301), (1, 2), (2, 3), (9, 10), (4, 5), (8, 9), (6, 7), (7, 71)]);
+}
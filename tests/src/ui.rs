@@ -0,0 +1,6 @@
+// TRYBUILD=overwrite cargo test --manifest-path ./tests/Cargo.toml ui
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("ui/*.rs");
+}
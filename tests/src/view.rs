@@ -0,0 +1,89 @@
+// cargo expand --manifest-path ./tests/Cargo.toml view
+use indexmap::IndexSet;
+use mut_set::{Item, MutSetExt};
+
+#[derive(Debug, Clone)]
+#[mut_set::derive::item(view = "ByName", id(name))]
+#[mut_set::derive::item(view = "ByPos", id(x, y))]
+pub(super) struct Cell {
+    pub name: String,
+    pub area: f64,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Views of an item keep its own id
+#[mut_set::derive::item]
+#[mut_set::derive::item(view = "PinByNet", id(layer, net), readonly = "PinByNetMut")]
+#[derive(Debug)]
+pub(super) struct Pin {
+    #[id]
+    pub name: String,
+    pub cap: f64,
+    pub layer: u8,
+    pub net: u64,
+}
+
+#[derive(Debug)]
+#[mut_set::derive::item(view = "ByKey", id(key, index))]
+pub(super) struct Entry<K, V> {
+    pub key: K,
+    pub index: u32,
+    pub value: V,
+}
+
+#[test]
+fn same_data() {
+    let cells = [("a", 0, 0), ("b", 0, 1), ("c", 1, 0)].map(|(name, x, y)| Cell {
+        name: name.into(),
+        area: 1.0,
+        x,
+        y,
+    });
+    let mut by_name: IndexSet<ByName> = cells.iter().cloned().map(ByName).collect();
+    let mut by_pos: IndexSet<ByPos> = cells.into_iter().map(ByPos::from).collect();
+
+    by_name.get_mut(&"b".to_string()).unwrap().area = 2.0;
+    let id: &ByNameId = by_name[1].id();
+    assert_eq!(id, "b");
+    assert_eq!(by_name[1].x, 0);
+
+    let id = ByPosId::new(0, 1);
+    let cell = by_pos.get_mut(&id).unwrap();
+    cell.area = 3.0;
+    assert_eq!((cell.x, cell.y), (0, 1));
+    assert_eq!(by_pos.get(&id).unwrap().name, "b");
+    assert_eq!(by_pos.get(&id).unwrap().area, 3.0);
+    assert_eq!(by_pos[2].id(), &ByPosId::from((1, 0)));
+    assert_eq!(ByPosId::from(&by_pos[2]), ByPosId::new(1, 0));
+    assert!(ByPosId::new(0, 1) < ByPosId::new(1, 0));
+    assert_eq!(format!("{id}"), "(0, 1)");
+    assert_eq!(<(i32, i32)>::from(id), (0, 1));
+    assert!(!by_pos.insert(ByPos(Cell { name: "d".into(), area: 1.0, x: 1, y: 0 })));
+}
+
+#[test]
+fn item_view() {
+    let pins = [("a", 1, 7), ("b", 1, 8), ("c", 2, 7)].map(|(name, layer, net)| Pin {
+        name: name.into(),
+        cap: 1.0,
+        layer,
+        net,
+    });
+    let mut by_net: IndexSet<PinByNet> = pins.into_iter().map(PinByNet).collect();
+    let pin: &mut PinByNetMut = by_net.get_mut(&PinByNetId::new(2, 7)).unwrap();
+    pin.cap = 2.0;
+    assert_eq!(by_net[2].0.id(), "c");
+    assert_eq!(by_net[2].cap, 2.0);
+    let pins: IndexSet<Pin> = by_net.into_iter().map(|pin| pin.0).collect();
+    assert_eq!(pins.get(&"c".to_string()).unwrap().cap, 2.0);
+}
+
+#[test]
+fn generic_view() {
+    let mut entries: IndexSet<ByKey<&str, Vec<u8>>> = IndexSet::new();
+    entries.insert(ByKey(Entry { key: "a", index: 0, value: vec![] }));
+    entries.insert(ByKey(Entry { key: "a", index: 1, value: vec![] }));
+    entries.get_mut(&ByKeyId::new("a", 1)).unwrap().value.push(1);
+    assert_eq!(entries[1].value, [1]);
+}
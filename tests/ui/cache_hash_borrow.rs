@@ -0,0 +1,7 @@
+#[mut_set::derive::item(cache_hash)]
+struct Named {
+    #[id(borrow = str)]
+    name: String,
+}
+
+fn main() {}
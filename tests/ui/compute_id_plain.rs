@@ -0,0 +1,7 @@
+#[mut_set::derive::item(compute_id = |id| id.name.to_lowercase())]
+struct Cell {
+    #[id]
+    name: String,
+}
+
+fn main() {}
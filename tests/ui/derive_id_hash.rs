@@ -0,0 +1,9 @@
+#[mut_set::derive::item(derive_id(Hash))]
+struct MyItem {
+    #[id]
+    id1: usize,
+    #[id]
+    id2: usize,
+}
+
+fn main() {}
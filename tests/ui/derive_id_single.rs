@@ -0,0 +1,7 @@
+#[mut_set::derive::item(derive_id(Default))]
+struct MyItem {
+    #[id]
+    id: usize,
+}
+
+fn main() {}
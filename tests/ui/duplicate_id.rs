@@ -0,0 +1,8 @@
+#[mut_set::derive::item]
+struct MyItem {
+    #[id]
+    #[id(borrow = str)]
+    id: String,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[mut_set::derive::item]
+struct MyItem {
+    #[id(borrow = str, borrow = String)]
+    id: String,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[mut_set::derive::item(id = "MyKey", id = "MyId")]
+struct MyItem {
+    #[id]
+    id: usize,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[mut_set::derive::item]
+enum MyItem {
+    A,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use std::hash::{Hash, Hasher};
+
+fn hash<H: Hasher>(val: &String, state: &mut H) {
+    val.to_ascii_lowercase().hash(state);
+}
+
+fn eq(a: &String, b: &String) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+#[mut_set::derive::item]
+struct MyItem {
+    #[id(hash_with = hash, eq_with = eq)]
+    id: String,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+fn eq(a: &String, b: &String) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+#[mut_set::derive::item]
+struct MyItem {
+    #[id(eq_with = eq)]
+    id: String,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct CellKey {
+    lib: String,
+    name: String,
+}
+
+#[mut_set::derive::item]
+struct Pin {
+    #[id(flatten)]
+    cell: CellKey,
+    #[id]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+#[repr(C)]
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct CellKey {
+    name: String,
+    lib: String,
+}
+
+#[mut_set::derive::item]
+struct Pin {
+    #[id(flatten(lib: String, name: String))]
+    cell: CellKey,
+    #[id]
+    pin: String,
+}
+
+fn main() {}
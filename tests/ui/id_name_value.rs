@@ -0,0 +1,7 @@
+#[mut_set::derive::item]
+struct MyItem {
+    #[id = "id"]
+    id: usize,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[mut_set::derive::item(id_vis = pub(crate))]
+pub struct Route {
+    #[id]
+    pub method: String,
+    #[id]
+    pub path: String,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use mut_set::intern::Symbol;
+
+#[mut_set::derive::item]
+struct Pin {
+    #[id(intern)]
+    cell: Symbol,
+    #[id]
+    name: String,
+}
+
+fn main() {}
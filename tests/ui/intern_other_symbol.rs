@@ -0,0 +1,10 @@
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Symbol(u32);
+
+#[mut_set::derive::item]
+struct Pin {
+    #[id(intern)]
+    name: Symbol,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[mut_set::derive::item]
+struct Pin {
+    #[id(intern)]
+    name: String,
+    cap: f64,
+}
+
+fn main() {}
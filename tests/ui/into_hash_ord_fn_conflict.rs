@@ -0,0 +1,15 @@
+fn key(val: &String) -> &str {
+    val
+}
+
+fn cmp(a: &String, b: &String) -> core::cmp::Ordering {
+    a.cmp(b)
+}
+
+#[mut_set::derive::item]
+struct MyItem {
+    #[id(into_hash_ord_fn = key, cmp_with = cmp)]
+    id: String,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[mut_set::derive::item(id = "MyItem")]
+struct MyItem {
+    #[id]
+    id: usize,
+}
+
+fn main() {}
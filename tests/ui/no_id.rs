@@ -0,0 +1,6 @@
+#[mut_set::derive::item]
+struct MyItem {
+    id: usize,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[mut_set::derive::item]
+struct Named {
+    #[id(prehashed)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[mut_set::derive::item]
+struct Cell {
+    #[id(prehashed)]
+    x: u64,
+    #[id]
+    y: u64,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[mut_set::derive::item]
+#[repr(C)]
+struct MyItem {
+    #[id]
+    id: usize,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[mut_set::derive::item]
+struct Cell {
+    #[id(source)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[mut_set::derive::item]
+struct MyItem(#[id] usize, f64);
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[mut_set::derive::item]
+struct MyItem;
+
+fn main() {}
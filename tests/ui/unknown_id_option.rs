@@ -0,0 +1,7 @@
+#[mut_set::derive::item]
+struct MyItem {
+    #[id(hash_fn = core::hash::Hash::hash)]
+    id: String,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[mut_set::derive::item(name = "MyKey")]
+struct MyItem {
+    #[id]
+    id: usize,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#[mut_set::derive::item(view = "ByPos", id(x, y))]
+#[mut_set::derive::item]
+struct Pin {
+    #[id]
+    name: String,
+    x: i32,
+    y: i32,
+}
+
+fn main() {}
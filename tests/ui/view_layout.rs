@@ -0,0 +1,13 @@
+#[mut_set::derive::item(view = "ByPos", id(x, y))]
+struct Cell {
+    flag: u8,
+    x: u8,
+    y: u32,
+}
+
+fn main() {
+    use std::borrow::Borrow;
+
+    let cell = ByPos(Cell { flag: 0, x: 1, y: 2 });
+    let _: &ByPosId = cell.borrow();
+}
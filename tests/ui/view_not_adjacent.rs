@@ -0,0 +1,8 @@
+#[mut_set::derive::item(view = "ByPos", id(x, y))]
+struct Cell {
+    x: i32,
+    name: String,
+    y: i32,
+}
+
+fn main() {}
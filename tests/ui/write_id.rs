@@ -0,0 +1,19 @@
+mod item {
+    #[mut_set::derive::item]
+    pub struct MyItem {
+        #[id]
+        pub id: usize,
+        pub ctx: usize,
+    }
+}
+
+fn main() {
+    use mut_set::MutSetExt;
+
+    let mut set = indexmap::IndexSet::new();
+    set.insert(item::MyItem { id: 1, ctx: 0 });
+    for v in set.iter_mut() {
+        v.ctx = 1;
+        v.id = 2;
+    }
+}
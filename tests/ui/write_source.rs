@@ -0,0 +1,16 @@
+use mut_set::MutSetExt;
+
+#[mut_set::derive::item(compute_id = |id| id.name.to_lowercase())]
+struct Cell {
+    #[id(source)]
+    name: String,
+    area: f64,
+}
+
+fn main() {
+    let mut cells = indexmap::IndexSet::new();
+    cells.insert(Cell { name: "a".into(), area: 1.0 });
+    for cell in cells.iter_mut() {
+        cell.name = "b".into();
+    }
+}